@@ -0,0 +1,92 @@
+use crate::{interval::Interval, point::Point, vec3::Vec3};
+
+/// An axis-aligned bounding box, the region where the three intervals overlap.
+#[derive(Debug, Clone)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Aabb { x, y, z }
+    }
+
+    /// The box spanning the two opposite corners `a` and `b`.
+    pub fn from_points(a: &Point, b: &Point) -> Self {
+        Aabb {
+            x: Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            y: Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            z: Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Aabb::new(Interval::empty(), Interval::empty(), Interval::empty())
+    }
+
+    /// The smallest box containing both `a` and `b`.
+    pub fn enclosing(a: &Aabb, b: &Aabb) -> Self {
+        Aabb {
+            x: Interval::enclosing(&a.x, &b.x),
+            y: Interval::enclosing(&a.y, &b.y),
+            z: Interval::enclosing(&a.z, &b.z),
+        }
+    }
+
+    pub fn axis(&self, n: usize) -> &Interval {
+        match n {
+            0 => &self.x,
+            1 => &self.y,
+            _ => &self.z,
+        }
+    }
+
+    /// Grows every side by `delta` in total, split evenly between either end.
+    pub fn expand(&self, delta: f64) -> Self {
+        Aabb::new(
+            self.x.expand(delta),
+            self.y.expand(delta),
+            self.z.expand(delta),
+        )
+    }
+
+    /// Widens any side thinner than `delta` to `delta`, so that flat shapes still have some
+    /// volume.
+    pub fn pad(&self, delta: f64) -> Self {
+        let pad = |i: &Interval| {
+            if i.size() < delta {
+                i.expand(delta)
+            } else {
+                i.clone()
+            }
+        };
+        Aabb::new(pad(&self.x), pad(&self.y), pad(&self.z))
+    }
+
+    pub fn centroid(&self) -> Point {
+        Point::new(
+            (self.x.min + self.x.max) / 2.0,
+            (self.y.min + self.y.max) / 2.0,
+            (self.z.min + self.z.max) / 2.0,
+        )
+    }
+
+    /// The vector from the minimum corner to the maximum corner.
+    pub fn diagonal(&self) -> Vec3 {
+        Vec3::new(self.x.size(), self.y.size(), self.z.size())
+    }
+
+    /// The index of the axis along which the box is widest.
+    pub fn longest_axis(&self) -> usize {
+        let diagonal = self.diagonal();
+        if diagonal.x() > diagonal.y() && diagonal.x() > diagonal.z() {
+            0
+        } else if diagonal.y() > diagonal.z() {
+            1
+        } else {
+            2
+        }
+    }
+}
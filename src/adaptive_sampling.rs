@@ -0,0 +1,28 @@
+use crate::variance_estimator::VarianceEstimator;
+
+/// Stops taking samples in a pixel once its value is known well enough, so that flat areas like
+/// the sky take few samples and noisy ones like glossy reflections take many. A pixel never takes
+/// more than its sampler's samples per pixel.
+#[derive(Debug, Clone)]
+pub struct AdaptiveSampling {
+    /// Every pixel takes at least this many samples, however little they vary, so that rare
+    /// bright paths have a chance to show up before a pixel is judged.
+    pub min_samples: usize,
+    /// A pixel has converged once the 95% confidence interval for its luminance is narrower, on
+    /// each side, than this fraction of the luminance.
+    pub threshold: f64,
+}
+
+impl AdaptiveSampling {
+    pub fn new(min_samples: usize, threshold: f64) -> Self {
+        AdaptiveSampling {
+            min_samples: min_samples.max(2),
+            threshold,
+        }
+    }
+
+    /// True if the pixel whose sample luminances are in `estimator` can stop sampling.
+    pub fn converged(&self, estimator: &VarianceEstimator) -> bool {
+        estimator.count() >= self.min_samples && estimator.relative_error() <= self.threshold
+    }
+}
@@ -0,0 +1,12 @@
+use crate::description::Description;
+
+/// The shape of a lens's opening, which out of focus highlights (bokeh) take on.
+pub trait Aperture {
+    /// Maps a pair of uniform random numbers to a point on the opening, distributed in proportion
+    /// to how much light passes through there. Openings span at most `[-1, 1]` in either
+    /// direction, with +y up.
+    fn sample(&self, u: [f64; 2]) -> [f64; 2];
+
+    /// Adds the shape of the opening to `description`.
+    fn describe(&self, description: &mut Description);
+}
@@ -0,0 +1,41 @@
+use std::f64::consts::PI;
+
+use crate::description::Description;
+
+use super::aperture::Aperture;
+
+/// A perfectly round opening: the unit disk.
+pub struct Circular;
+
+impl Circular {
+    pub fn new() -> Self {
+        Circular
+    }
+}
+
+impl Default for Circular {
+    fn default() -> Self {
+        Circular::new()
+    }
+}
+
+impl Aperture for Circular {
+    /// Shirley and Chiu's concentric mapping, which keeps neighbouring samples close together
+    /// on the disk.
+    fn sample(&self, u: [f64; 2]) -> [f64; 2] {
+        let (a, b) = (2.0 * u[0] - 1.0, 2.0 * u[1] - 1.0);
+        if a == 0.0 && b == 0.0 {
+            return [0.0, 0.0];
+        }
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, PI / 4.0 * (b / a))
+        } else {
+            (b, PI / 2.0 - PI / 4.0 * (a / b))
+        };
+        [r * theta.cos(), r * theta.sin()]
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("circular");
+    }
+}
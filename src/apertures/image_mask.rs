@@ -0,0 +1,61 @@
+use crate::{
+    color::luminance, description::Description, distribution::Distribution2D, hdr_image::HdrImage,
+};
+
+use super::aperture::Aperture;
+
+/// An opening cut to any shape by a mask, whose luminance gives how much light passes through
+/// each part of it. The mask is stretched over `[-1, 1]` in both directions.
+pub struct ImageMask {
+    distribution: Distribution2D,
+}
+
+impl ImageMask {
+    /// Returns None if no light passes through any of the mask.
+    pub fn new(mask: &HdrImage) -> Option<Self> {
+        let transmission: Vec<f64> = (0..mask.height)
+            .flat_map(|y| (0..mask.width).map(move |x| (x, y)))
+            .map(|(x, y)| luminance(mask.pixel(x, y)).max(0.0))
+            .collect();
+        if transmission.iter().all(|t| *t <= 0.0) {
+            return None;
+        }
+        Some(ImageMask {
+            distribution: Distribution2D::new(&transmission, mask.width, mask.height),
+        })
+    }
+}
+
+impl Aperture for ImageMask {
+    fn sample(&self, u: [f64; 2]) -> [f64; 2] {
+        let ([x, y], _) = self.distribution.sample(u);
+        [2.0 * x - 1.0, 1.0 - 2.0 * y]
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("image mask");
+        self.distribution.describe(description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    #[test]
+    fn test_image_mask() {
+        // only the top right quarter lets light through
+        let black = Color::new(0.0, 0.0, 0.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let pixels = vec![black.clone(), white, black.clone(), black];
+        let mask = ImageMask::new(&HdrImage::new(2, 2, pixels).unwrap()).unwrap();
+
+        for i in 0..16 {
+            for j in 0..16 {
+                let [x, y] = mask.sample([(i as f64 + 0.5) / 16.0, (j as f64 + 0.5) / 16.0]);
+                assert!(x >= 0.0 && y >= 0.0);
+            }
+        }
+    }
+}
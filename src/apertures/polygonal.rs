@@ -0,0 +1,77 @@
+use std::f64::consts::PI;
+
+use crate::{deg_to_radians, description::Description};
+
+use super::aperture::Aperture;
+
+/// The regular polygon left open by an iris diaphragm's straight blades, inscribed in the unit
+/// circle.
+pub struct Polygonal {
+    blades: usize,
+    /// The angle of the first corner, anticlockwise from +x, in radians.
+    rotation: f64,
+}
+
+impl Polygonal {
+    /// An opening with `blades` sides, at least 3, turned anticlockwise by `rotation` degrees
+    /// from having a corner at +x.
+    pub fn new(blades: usize, rotation: f64) -> Self {
+        Polygonal {
+            blades: blades.max(3),
+            rotation: deg_to_radians(rotation),
+        }
+    }
+
+    fn corner(&self, i: usize) -> [f64; 2] {
+        let angle = self.rotation + 2.0 * PI * i as f64 / self.blades as f64;
+        [angle.cos(), angle.sin()]
+    }
+}
+
+impl Aperture for Polygonal {
+    /// Picks one of the equal triangles fanning out from the centre, and a uniform point in it.
+    fn sample(&self, u: [f64; 2]) -> [f64; 2] {
+        let scaled = u[0] * self.blades as f64;
+        let triangle = (scaled as usize).min(self.blades - 1);
+        let u0 = scaled - triangle as f64;
+
+        let a = self.corner(triangle);
+        let b = self.corner(triangle + 1);
+        // uniform barycentrics, folding the far half of the square back onto the triangle
+        let (s, t) = if u0 + u[1] > 1.0 {
+            (1.0 - u0, 1.0 - u[1])
+        } else {
+            (u0, u[1])
+        };
+        [s * a[0] + t * b[0], s * a[1] + t * b[1]]
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("polygonal")
+            .u64(self.blades as u64)
+            .f64(self.rotation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygonal() {
+        let hexagon = Polygonal::new(6, 90.0);
+        let apothem = (PI / 6.0).cos();
+        let mut max_y: f64 = 0.0;
+        for i in 0..64 {
+            for j in 0..64 {
+                let [x, y] = hexagon.sample([(i as f64 + 0.5) / 64.0, (j as f64 + 0.5) / 64.0]);
+                // with a corner at the top, the flat sides are on the left and right
+                assert!(x.abs() <= apothem + 1e-12);
+                assert!(x * x + y * y <= 1.0 + 1e-12);
+                max_y = max_y.max(y);
+            }
+        }
+        assert!(max_y > 0.95);
+    }
+}
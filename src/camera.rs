@@ -1,61 +1,357 @@
-use std::cmp::max;
-
-use rand::{rngs::ThreadRng, Rng};
+use std::{
+    cmp::max,
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
 
 use crate::{
-    color::Color, hittable::Hittable, interval::Interval, point::Point, ray::Ray, vec3::Vec3,
+    adaptive_sampling::AdaptiveSampling,
+    checkpoint::Checkpoint,
+    color::{luminance, Color},
+    description::Description,
+    film::{write_atomically, Film},
+    filters::{box_filter::BoxFilter, filter::Filter, filter_sampler::FilterSampler},
+    integrator::Integrator,
+    light_samplers::light_sampler::LightSampler,
+    onb::Onb,
+    pcg::hash,
+    point::Point,
+    progressive::{Progressive, StopReason},
+    projections::{perspective::Perspective, projection::Projection},
+    ray::Ray,
+    samplers::{
+        independent::Independent,
+        sampler::{pixel_key, Sampler},
+    },
+    scene::Scene,
+    stereo::Stereo,
+    vec3::{cross, Vec3},
 };
 
 pub struct Camera {
     image_width: i32,
     image_height: i32,
     centre: Point,
-    top_left_pixel_loc: Point,
-    pixel_delta_u: Vec3,
-    pixel_delta_v: Vec3,
-    pixel_samples: i32,
-    rng: ThreadRng,
+    /// The camera's axes: `u` to the right, `v` up and `w` backwards, away from the view.
+    orientation: Onb,
+    pub projection: Box<dyn Projection>,
+    /// Renders a view for each eye side by side or over and under, if set.
+    pub stereo: Option<Stereo>,
+    /// Supplies the random numbers for every sample, and decides how many each pixel takes.
+    /// Samples depend only on the sampler's seed, the pixel and the sample's index, so renders
+    /// with the same sampler and seed match bit for bit. Defaults to 100 independent samples with
+    /// a seed of 0.
+    pub sampler: Box<dyn Sampler>,
+    /// Lets pixels stop short of the sampler's samples per pixel once they've converged, if set.
+    pub adaptive_sampling: Option<AdaptiveSampling>,
+    /// Weights each sample by its offset from the centre of the pixel it's for. Defaults to a box
+    /// filling the pixel, which averages the samples within it.
+    pub filter: Box<dyn Filter>,
+    /// The interval over which the shutter is open. Each ray is given a random time within it, so
+    /// hittables that move while it's open are blurred. Defaults to an instant at time 0.
+    pub shutter_open: f64,
+    pub shutter_close: f64,
+    /// The image being rendered, which every sample is accumulated into.
+    film: Film,
 }
 
 impl Camera {
-    pub fn render(&mut self, hittables: &[&dyn Hittable], reflection_depth: i32) -> String {
-        let mut ppm = String::new();
-        ppm.push_str(format!("P3\n{} {}\n255\n", self.image_width, self.image_height).as_str());
+    /// A camera at the origin looking down -z, with +y up.
+    pub fn new(image_width: i32, image_height: i32, projection: Box<dyn Projection>) -> Self {
+        let (image_width, image_height) = (max(image_width, 1), max(image_height, 1));
+        Camera {
+            image_width,
+            image_height,
+            centre: Point::new(0.0, 0.0, 0.0),
+            orientation: Onb {
+                u: Vec3::new(1.0, 0.0, 0.0),
+                v: Vec3::new(0.0, 1.0, 0.0),
+                w: Vec3::new(0.0, 0.0, 1.0),
+            },
+            projection,
+            stereo: None,
+            sampler: Box::new(Independent::new(100, 0)),
+            adaptive_sampling: None,
+            filter: Box::new(BoxFilter::new(0.5)),
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            film: Film::new(image_width, image_height),
+        }
+    }
+
+    /// Moves the camera to `look_from` and turns it to face `look_at`, rolled so that `up` points
+    /// up in the image as far as it can. If `up` is parallel to the view, which leaves the roll
+    /// undecided, an arbitrary roll is chosen.
+    pub fn look_at(&mut self, look_from: Point, look_at: &Point, up: &Vec3) {
+        let w = (&look_from - look_at).unit();
+        let u = cross(up.clone(), w.clone());
+        let u = if u.is_near_zero() {
+            Onb::new(&w).u
+        } else {
+            u.unit()
+        };
+        let v = cross(w.clone(), u.clone());
+        self.centre = look_from;
+        self.orientation = Onb { u, v, w };
+    }
+
+    /// Renders the scene with the sampler's samples per pixel, returning it as a PPM image.
+    pub fn render(&mut self, scene: &Scene, integrator: &Integrator) -> String {
+        self.film = Film::new(self.image_width, self.image_height);
+        let light_sampler = integrator.light_sampler(scene);
+        let filter_sampler = FilterSampler::new(self.filter.as_ref());
+        let samples = self.sampler.samples_per_pixel();
+        self.render_pass(
+            scene,
+            integrator,
+            light_sampler.as_ref(),
+            &filter_sampler,
+            samples,
+            &|| false,
+        );
+        print!("\r");
+        self.film.to_ppm()
+    }
+
+    /// Renders the scene in passes, writing the image to `progressive.snapshot_path`, and a
+    /// checkpoint if asked for, after each one, until every pixel has taken the sampler's samples
+    /// per pixel, the time budget runs out or `interrupted` is set. Carries on from whatever the
+    /// film already holds.
+    pub fn render_progressive(
+        &mut self,
+        scene: &Scene,
+        integrator: &Integrator,
+        progressive: &Progressive,
+        interrupted: &AtomicBool,
+    ) -> io::Result<StopReason> {
+        let start = Instant::now();
+        let light_sampler = integrator.light_sampler(scene);
+        let filter_sampler = FilterSampler::new(self.filter.as_ref());
+        let target = self.sampler.samples_per_pixel();
+        let out_of_time = || {
+            progressive
+                .time_budget
+                .is_some_and(|budget| start.elapsed() >= budget)
+        };
+        let stopping = || interrupted.load(Ordering::Relaxed) || out_of_time();
+        let scene_hash = self.scene_hash(scene, integrator);
+
+        let mut pass = 0;
+        while self
+            .film
+            .pixels
+            .iter()
+            .any(|pixel| !pixel.converged && pixel.sample_count() < target)
+        {
+            if stopping() {
+                break;
+            }
+            pass += 1;
+            print!("\rpass {}, {} spp ", pass, self.film.min_sample_count());
+            self.render_pass(
+                scene,
+                integrator,
+                light_sampler.as_ref(),
+                &filter_sampler,
+                progressive.pass_samples,
+                &stopping,
+            );
+            self.save_progress(progressive, scene_hash)?;
+        }
+        print!("\r");
+        self.save_progress(progressive, scene_hash)?;
 
+        Ok(if interrupted.load(Ordering::Relaxed) {
+            StopReason::Interrupted
+        } else if out_of_time() {
+            StopReason::OutOfTime
+        } else {
+            StopReason::Finished
+        })
+    }
+
+    /// The image being rendered, or last rendered.
+    pub fn film(&self) -> &Film {
+        &self.film
+    }
+
+    /// Carries on from `checkpoint` with the next progressive render, after checking that it's
+    /// of the same scene, seen the same way, as this camera would render now.
+    pub fn resume(
+        &mut self,
+        checkpoint: Checkpoint,
+        scene: &Scene,
+        integrator: &Integrator,
+    ) -> io::Result<()> {
+        let film = &checkpoint.film;
+        if film.width() != self.image_width || film.height() != self.image_height {
+            return Err(invalid_input(format!(
+                "checkpoint is {}x{}, but the image is {}x{}",
+                film.width(),
+                film.height(),
+                self.image_width,
+                self.image_height
+            )));
+        }
+        if checkpoint.seed != self.sampler.seed()
+            || checkpoint.samples_per_pixel != self.sampler.samples_per_pixel()
+        {
+            return Err(invalid_input(format!(
+                "checkpoint was rendered with seed {} at {} spp, but the sampler has seed {} at {} spp",
+                checkpoint.seed,
+                checkpoint.samples_per_pixel,
+                self.sampler.seed(),
+                self.sampler.samples_per_pixel()
+            )));
+        }
+        if checkpoint.scene_hash != self.scene_hash(scene, integrator) {
+            return Err(invalid_input(
+                "checkpoint is of a different scene, or the camera or integrator has changed"
+                    .to_string(),
+            ));
+        }
+        self.film = checkpoint.film;
+        Ok(())
+    }
+
+    /// A hash of the scene and of how it's rendered, for telling whether either has changed since a
+    /// checkpoint was saved: everything in the scene as `Description` describes it, along with the
+    /// camera, its sampler and filter, and the integrator's settings.
+    pub fn scene_hash(&self, scene: &Scene, integrator: &Integrator) -> u64 {
+        let mut description = Description::new();
+        description
+            .u64(self.image_width as u64)
+            .u64(self.image_height as u64)
+            .vec3(&self.centre)
+            .vec3(&self.orientation.u)
+            .vec3(&self.orientation.v)
+            .vec3(&self.orientation.w)
+            .f64(self.shutter_open)
+            .f64(self.shutter_close);
+        self.projection.describe(&mut description);
+        description.u64(self.stereo.is_some() as u64);
+        if let Some(stereo) = &self.stereo {
+            stereo.describe(&mut description);
+        }
+        self.sampler.describe(&mut description);
+        self.filter.describe(&mut description);
+        description.u64(self.adaptive_sampling.is_some() as u64);
+        if let Some(adaptive) = &self.adaptive_sampling {
+            description
+                .u64(adaptive.min_samples as u64)
+                .f64(adaptive.threshold);
+        }
+        integrator.describe(&mut description);
+        scene.describe(&mut description);
+        description.hash()
+    }
+
+    /// A heatmap of the number of samples each pixel took, as a PPM image.
+    pub fn sample_count_heatmap(&self) -> String {
+        self.film.sample_count_heatmap()
+    }
+
+    /// Writes the image, and a checkpoint if `progressive` asks for one.
+    fn save_progress(&self, progressive: &Progressive, scene_hash: u64) -> io::Result<()> {
+        write_atomically(&progressive.snapshot_path, self.film.to_ppm())?;
+        let Some(checkpoint_path) = &progressive.checkpoint_path else {
+            return Ok(());
+        };
+        let checkpoint = Checkpoint {
+            scene_hash,
+            seed: self.sampler.seed(),
+            samples_per_pixel: self.sampler.samples_per_pixel(),
+            film: self.film.clone(),
+        };
+        checkpoint.save(checkpoint_path)
+    }
+
+    /// Adds up to `samples` samples to every pixel of the film that hasn't converged or taken the
+    /// sampler's samples per pixel, stopping between rows if `stop` returns true.
+    fn render_pass(
+        &mut self,
+        scene: &Scene,
+        integrator: &Integrator,
+        light_sampler: &dyn LightSampler,
+        filter_sampler: &FilterSampler,
+        samples: usize,
+        stop: &dyn Fn() -> bool,
+    ) {
+        let target = self.sampler.samples_per_pixel();
+        let mut film = std::mem::take(&mut self.film);
         for y in 0..self.image_height {
+            if stop() {
+                break;
+            }
             print!("\r{}", progress_bar(y, self.image_height));
             for x in 0..self.image_width {
-                // start the pixel as black, and we'll aggregate the values of each pixel sample
-                // into it
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-                for _ in 0..self.pixel_samples {
-                    let ray = self.get_offset_ray(x, y);
-                    pixel_color += ray.color(hittables, reflection_depth);
+                let pixel = &mut film.pixels[(y * self.image_width + x) as usize];
+                if pixel.converged {
+                    continue;
+                }
+                // each pixel carries on from the samples it already has, so its sample indices
+                // run on from one pass to the next
+                let first = pixel.sample_count();
+                for sample_index in first..target.min(first + samples) {
+                    self.sampler.start_pixel_sample([x, y], sample_index);
+                    let (offset, filter_weight) = filter_sampler.sample(self.sampler.get_2d());
+                    pixel.weight_sum += filter_weight;
+                    let mut contribution = Color::new(0.0, 0.0, 0.0);
+                    if let Some((mut ray, weight)) = self.get_offset_ray(x, y, offset) {
+                        ray.sample_seed =
+                            hash(&[self.sampler.seed(), pixel_key([x, y]), sample_index as u64]);
+                        let color =
+                            ray.color(scene, integrator, light_sampler, self.sampler.as_mut());
+                        contribution = color * (weight * filter_weight);
+                    }
+                    // the luminance each sample adds to the pixel tells when it has converged
+                    pixel.estimator.add(luminance(&contribution));
+                    pixel.color_sum += contribution;
+
+                    pixel.converged = self
+                        .adaptive_sampling
+                        .as_ref()
+                        .is_some_and(|adaptive| adaptive.converged(&pixel.estimator));
+                    if pixel.converged {
+                        break;
+                    }
                 }
-                let scale = 1.0 / self.pixel_samples as f64;
-                pixel_color *= scale;
-                ppm.push_str(write_color(&pixel_color).as_str());
             }
         }
-
-        print!("\r");
-        ppm
+        self.film = film;
     }
 
-    /// Returns a Ray originating at the camera centre and passing through a randomly smapled point
-    /// around the pixel located at (x, y)
-    fn get_offset_ray(&mut self, x: i32, y: i32) -> Ray {
-        // get a random offset in the pixel's unit square, bound by `pixel_delta_u/v`
-        let offset_y: f64 = self.rng.gen_range(-0.5..0.5);
-        let offset_x: f64 = self.rng.gen_range(-0.5..0.5);
+    /// Returns a Ray through the point `offset` pixels from the centre of the pixel at (x, y) and
+    /// the weight of the light it brings back, or None if the projection doesn't cover that point.
+    fn get_offset_ray(&mut self, x: i32, y: i32, offset: [f64; 2]) -> Option<(Ray, f64)> {
+        // with stereo, each eye's view takes up part of the image
+        let (stereo_eye, [x, y, width, height]) = match &self.stereo {
+            Some(stereo) => {
+                let (eye, view) = stereo.split(x, y, self.image_width, self.image_height);
+                (Some((stereo, eye)), view)
+            }
+            None => (None, [x, y, self.image_width, self.image_height]),
+        };
 
-        let pixel_sample = &self.top_left_pixel_loc
-            + &(&self.pixel_delta_u * (x as f64 + offset_x))
-            + (&self.pixel_delta_v * (y as f64 + offset_y));
+        let film = [
+            (x as f64 + 0.5 + offset[0]) / width as f64,
+            (y as f64 + 0.5 + offset[1]) / height as f64,
+        ];
+        let aspect_ratio = width as f64 / height as f64;
+        let lens = self.sampler.get_2d();
+        let time = self.sampler.get_1d();
+        let mut camera_ray = self.projection.generate_ray(film, lens, aspect_ratio)?;
+        if let Some((stereo, eye)) = stereo_eye {
+            (camera_ray.origin, camera_ray.direction) =
+                stereo.eye_ray(eye, &camera_ray.origin, &camera_ray.direction);
+        }
 
-        let ray_origin = Point::new(self.centre.x(), self.centre.y(), self.centre.z());
-        let ray_direction = &pixel_sample - &self.centre;
-        Ray::new(ray_origin, ray_direction)
+        let ray_origin = &self.centre + &self.orientation.to_world(&camera_ray.origin);
+        let ray_direction = self.orientation.to_world(&camera_ray.direction);
+        let ray_time = self.shutter_open + time * (self.shutter_close - self.shutter_open);
+        let ray = Ray::new(ray_origin, ray_direction, ray_time);
+        Some((ray, camera_ray.weight))
     }
 }
 
@@ -63,42 +359,15 @@ impl Default for Camera {
     fn default() -> Self {
         let aspect_ratio = 16.0 / 9.0;
         let image_width = 400;
-        let provisional_height = (image_width as f64 / aspect_ratio) as i32;
-        let image_height = max(provisional_height, 1);
-
-        // camera
-        let focal_length = 1.0; // distance between the camera centre and the centre of the viewport
-        let viewport_height = 2.0;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
-        let centre = Point::new(0.0, 0.0, 0.0);
-
-        // vectors across the horizontal and vertical space of the viewport
-        let viewport_u = Vec3::new(viewport_width, 0.0, 0.0);
-        let viewport_v = Vec3::new(0.0, -viewport_height, 0.0);
-
-        // horizontal and vertical delta vectors between pixels; the distance between pixels in the
-        // viewport
-        let pixel_delta_u = &viewport_u / image_width as f64;
-        let pixel_delta_v = &viewport_v / image_height as f64;
-
-        // location of the top-left pixel. piexels should be inset by 1/2 * delta_v/delta_u
-        let viewport_centre = &centre - &Vec3::new(0.0, 0.0, focal_length);
-        let viewport_top_left = viewport_centre - viewport_u / 2.0 - viewport_v / 2.0;
-        let top_left_pixel_loc = viewport_top_left + (&pixel_delta_u + &pixel_delta_v) * 0.5;
-
-        Camera {
-            image_width,
-            image_height,
-            centre,
-            top_left_pixel_loc,
-            pixel_delta_u,
-            pixel_delta_v,
-            pixel_samples: 100,
-            rng: rand::thread_rng(),
-        }
+        let image_height = (image_width as f64 / aspect_ratio) as i32;
+        Camera::new(image_width, image_height, Box::new(Perspective::new(90.0)))
     }
 }
 
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
 fn progress_bar(curr: i32, of: i32) -> String {
     let width = 80;
     let percent = curr as f64 / of as f64;
@@ -106,28 +375,257 @@ fn progress_bar(curr: i32, of: i32) -> String {
     let empty = width - fill;
     format!(
         "[{}{}{}] {}%",
-        "=".repeat(fill as usize),
+        "=".repeat(fill),
         ">",
-        "·".repeat(empty as usize),
+        "·".repeat(empty),
         (percent * 100.0) as i32
     )
 }
 
-fn write_color(pixel_color: &Color) -> String {
-    let r = linear_to_gamma(pixel_color.x());
-    let g = linear_to_gamma(pixel_color.y());
-    let b = linear_to_gamma(pixel_color.z());
-    // translate the pixel's color value from the range [0, 1] -> [0, 255]
-    let intensity = Interval::new(0.0, 0.999);
-    let rbyte = (256.0 * intensity.clamp(r)) as i32;
-    let gbyte = (256.0 * intensity.clamp(g)) as i32;
-    let bbyte = (256.0 * intensity.clamp(b)) as i32;
-    format!("{} {} {}\n", rbyte, gbyte, bbyte)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constant_medium::ConstantMedium,
+        environments::{constant::Constant, gradient::Gradient},
+        hittable::Hittable,
+        materials::{diffuse_light::DiffuseLight, lambertian::Lambertian},
+        quad::Quad,
+        samplers::sobol::Sobol,
+        sphere::Sphere,
+        vec3::dot,
+    };
+
+    #[test]
+    fn test_look_at() {
+        let mut camera = Camera::new(4, 4, Box::new(Perspective::new(90.0)));
+        camera.look_at(
+            Point::new(0.0, 0.0, 0.0),
+            &Point::new(1.0, 0.0, 0.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert!((&camera.orientation.w - &Vec3::new(-1.0, 0.0, 0.0)).len() < 1e-12);
+        assert!((&camera.orientation.v - &Vec3::new(0.0, 1.0, 0.0)).len() < 1e-12);
+
+        // looking straight along `up` still gives an orthonormal frame facing the target
+        camera.look_at(
+            Point::new(0.0, 5.0, 0.0),
+            &Point::new(0.0, 0.0, 0.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        let Onb { u, v, w } = &camera.orientation;
+        assert!((w - &Vec3::new(0.0, 1.0, 0.0)).len() < 1e-12);
+        for (a, b) in [(u, v), (v, w), (w, u)] {
+            assert!(dot(a, b).abs() < 1e-12);
+        }
+        assert!((u.len() - 1.0).abs() < 1e-12 && (v.len() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_adaptive_sampling() {
+        // the left half of the image sees a black sky, and the right half a lit wall
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let light = DiffuseLight::new(Color::new(4.0, 4.0, 4.0));
+        let wall = Quad::new(
+            Point::new(0.0, -10.0, -1.0),
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(0.0, 20.0, 0.0),
+            &grey,
+        );
+        let lamp = Quad::new(
+            Point::new(3.0, -2.0, -0.5),
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::new(0.0, 4.0, 0.0),
+            &light,
+        );
+        let hittables: [&dyn Hittable; 2] = [&wall, &lamp];
+        let lights: [&dyn Hittable; 1] = [&lamp];
+        let sky = Constant::new(Color::new(0.0, 0.0, 0.0));
+        let scene = Scene::new(&hittables, &lights, &sky);
+        let integrator = Integrator::default();
+
+        let mut camera = Camera::new(8, 4, Box::new(Perspective::new(90.0)));
+        camera.sampler = Box::new(Independent::new(64, 0));
+        camera.adaptive_sampling = Some(AdaptiveSampling::new(4, 0.001));
+        camera.render(&scene, &integrator);
+
+        let film = camera.film();
+        for y in 0..4 {
+            for x in 0..8 {
+                let pixel = &film.pixels[y * 8 + x];
+                let expected = if x < 4 { 4 } else { 64 };
+                assert_eq!(expected, pixel.sample_count(), "pixel ({x}, {y})");
+            }
+        }
+
+        // the flat pixels take the fewest samples and are black, the noisy ones white
+        let heatmap = camera.sample_count_heatmap();
+        let mut lines = heatmap.lines();
+        assert_eq!(Some("P3"), lines.next());
+        assert_eq!(Some("8 4"), lines.next());
+        assert_eq!(Some("255"), lines.next());
+        for (i, line) in lines.enumerate() {
+            let expected = if i % 8 < 4 { "0 0 0" } else { "255 255 255" };
+            assert_eq!(expected, line);
+        }
+    }
+
+    #[test]
+    fn test_seeded_render_is_reproducible() {
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let light = DiffuseLight::new(Color::new(4.0, 4.0, 4.0));
+        let floor = Sphere::new(Point::new(0.0, -100.5, -1.0), 100.0, &grey);
+        let lamp = Quad::new(
+            Point::new(-0.5, 1.0, -1.5),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            &light,
+        );
+        let boundary = Sphere::new(Point::new(0.0, 0.0, -1.0), 0.5, &grey);
+        let fog = ConstantMedium::new(&boundary, 2.0, Color::new(0.9, 0.9, 0.9)).unwrap();
+        let hittables: [&dyn Hittable; 3] = [&floor, &lamp, &fog];
+        let lights: [&dyn Hittable; 1] = [&lamp];
+        let sky = Gradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0));
+        let scene = Scene::new(&hittables, &lights, &sky);
+        let integrator = Integrator::default();
+
+        let render = |sampler: Box<dyn Sampler>| {
+            let mut camera = Camera::new(12, 8, Box::new(Perspective::new(60.0)));
+            camera.sampler = sampler;
+            camera.render(&scene, &integrator)
+        };
+        let first = render(Box::new(Independent::new(4, 7)));
+        assert_eq!(first, render(Box::new(Independent::new(4, 7))));
+        assert_ne!(first, render(Box::new(Independent::new(4, 8))));
+        assert_eq!(
+            render(Box::new(Sobol::new(4, 7))),
+            render(Box::new(Sobol::new(4, 7)))
+        );
+    }
+
+    #[test]
+    fn test_progressive_passes_match_one_render() {
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let ball = Sphere::new(Point::new(0.0, 0.0, -1.0), 0.5, &grey);
+        let hittables: [&dyn Hittable; 1] = [&ball];
+        let sky = Gradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0));
+        let scene = Scene::new(&hittables, &[], &sky);
+        let integrator = Integrator::default();
+        let new_camera = || {
+            let mut camera = Camera::new(8, 6, Box::new(Perspective::new(60.0)));
+            camera.sampler = Box::new(Independent::new(6, 3));
+            camera
+        };
+
+        // each pixel's samples carry on where the last pass left off, so passes of 4 samples add
+        // up to the same image as all 6 at once
+        let path = std::env::temp_dir().join(format!(
+            "ray-tracing-test-progressive-{}.ppm",
+            std::process::id()
+        ));
+        let mut camera = new_camera();
+        let progressive = Progressive::new(4, &path);
+        let reason = camera
+            .render_progressive(&scene, &integrator, &progressive, &AtomicBool::new(false))
+            .unwrap();
+        assert_eq!(reason, StopReason::Finished);
+        let snapshot = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(snapshot, new_camera().render(&scene, &integrator));
+    }
+
+    #[test]
+    fn test_resume_rejects_changed_scene() {
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let red = Lambertian::new(Color::new(0.5, 0.1, 0.1));
+        let sky = Gradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0));
+        let integrator = Integrator::default();
+        let new_camera = || {
+            let mut camera = Camera::new(8, 6, Box::new(Perspective::new(60.0)));
+            camera.sampler = Box::new(Independent::new(4, 3));
+            camera
+        };
+
+        let ball = Sphere::new(Point::new(0.0, 0.0, -1.0), 0.5, &grey);
+        let hittables: [&dyn Hittable; 1] = [&ball];
+        let scene = Scene::new(&hittables, &[], &sky);
+        let scene_hash = new_camera().scene_hash(&scene, &integrator);
+        let checkpoint = || Checkpoint {
+            scene_hash,
+            seed: 3,
+            samples_per_pixel: 4,
+            film: Film::new(8, 6),
+        };
+        assert!(new_camera()
+            .resume(checkpoint(), &scene, &integrator)
+            .is_ok());
+
+        let red_ball = Sphere::new(Point::new(0.0, 0.0, -1.0), 0.5, &red);
+        let hittables: [&dyn Hittable; 1] = [&red_ball];
+        let changed = Scene::new(&hittables, &[], &sky);
+        assert!(new_camera()
+            .resume(checkpoint(), &changed, &integrator)
+            .is_err());
+
+        let mut moved = new_camera();
+        moved.look_at(
+            Point::new(0.0, 0.0, 1.0),
+            &Point::new(0.0, 0.0, -1.0),
+            &Vec3::new(0.0, 1.0, 0.0),
+        );
+        assert!(moved.resume(checkpoint(), &scene, &integrator).is_err());
+    }
+
+    #[test]
+    fn test_scene_hash_describes_scene_and_settings() {
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let red = Lambertian::new(Color::new(0.5, 0.1, 0.1));
+        let sky = Constant::new(Color::new(0.0, 0.0, 0.0));
+        let integrator = Integrator::default();
+        // red and green lamps of the same luminance, and so the same power, behind the camera
+        let red_light = DiffuseLight::new(Color::new(1.0 / 0.2126, 0.0, 0.0));
+        let green_light = DiffuseLight::new(Color::new(0.0, 1.0 / 0.7152, 0.0));
+        let quad = |material| {
+            Quad::new(
+                Point::new(-0.5, -0.5, 2.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                material,
+            )
+        };
+        let (red_lamp, green_lamp) = (quad(&red_light), quad(&green_light));
+        assert_eq!(red_lamp.power(), green_lamp.power());
+        // and a ball behind the camera too
+        let grey_ball = Sphere::new(Point::new(0.0, 0.0, 3.0), 0.5, &grey);
+        let red_ball = Sphere::new(Point::new(0.0, 0.0, 3.0), 0.5, &red);
+        let scene_hash = |camera: &Camera, ball: &Sphere, lamp: &Quad| {
+            let hittables: [&dyn Hittable; 2] = [ball, lamp];
+            let lights: [&dyn Hittable; 1] = [lamp];
+            camera.scene_hash(&Scene::new(&hittables, &lights, &sky), &integrator)
+        };
+        let new_camera = || {
+            let mut camera = Camera::new(8, 6, Box::new(Perspective::new(60.0)));
+            camera.sampler = Box::new(Independent::new(4, 3));
+            camera
+        };
+        let original = scene_hash(&new_camera(), &grey_ball, &red_lamp);
+        assert_eq!(original, scene_hash(&new_camera(), &grey_ball, &red_lamp));
+
+        // changes nothing the camera sees
+        assert_ne!(original, scene_hash(&new_camera(), &grey_ball, &green_lamp));
+        assert_ne!(original, scene_hash(&new_camera(), &red_ball, &red_lamp));
 
-fn linear_to_gamma(linear_component: f64) -> f64 {
-    if linear_component > 0.0 {
-        return linear_component.sqrt();
+        let mut sobol = new_camera();
+        sobol.sampler = Box::new(Sobol::new(4, 3));
+        assert_ne!(original, scene_hash(&sobol, &grey_ball, &red_lamp));
+        let mut wider = new_camera();
+        wider.filter = Box::new(BoxFilter::new(1.0));
+        assert_ne!(original, scene_hash(&wider, &grey_ball, &red_lamp));
+        let mut adaptive = new_camera();
+        adaptive.adaptive_sampling = Some(AdaptiveSampling::new(4, 0.05));
+        assert_ne!(original, scene_hash(&adaptive, &grey_ball, &red_lamp));
+        let mut wide_angle = new_camera();
+        wide_angle.projection = Box::new(Perspective::new(90.0));
+        assert_ne!(original, scene_hash(&wide_angle, &grey_ball, &red_lamp));
     }
-    0.0
 }
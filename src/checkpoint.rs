@@ -0,0 +1,241 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    color::Color,
+    film::{write_atomically, Film, FilmPixel},
+    variance_estimator::VarianceEstimator,
+};
+
+const MAGIC: &[u8; 8] = b"RTCHKPT\0";
+const VERSION: u32 = 1;
+/// The size of each pixel's entry: four f64s, a u64, two f64s and a u8.
+const PIXEL_BYTES: usize = 4 * 8 + 8 + 2 * 8 + 1;
+
+/// Everything needed to carry on with a progressive render after it has stopped: the film with
+/// each pixel's accumulated samples, and the state of the random numbers. Samples are derived
+/// from the sampler's seed, the pixel and the sample's index, so the seed together with each
+/// pixel's sample count is all the random state there is.
+///
+/// Checkpoints are stored in a little endian binary format:
+///
+/// - the magic bytes `RTCHKPT\0` and a u32 format version
+/// - a u64 hash of the scene and camera the render is of
+/// - the sampler's u64 seed and u64 samples per pixel
+/// - the u32 width and height of the film
+/// - for each pixel, row by row: its summed color and filter weight as four f64s, its sample
+///   count as a u64, the mean and sum of squared differences of its samples' luminance as f64s,
+///   and a u8 that's 1 if it has converged
+pub struct Checkpoint {
+    pub scene_hash: u64,
+    pub seed: u64,
+    pub samples_per_pixel: usize,
+    pub film: Film,
+}
+
+impl Checkpoint {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Checkpoint::from_bytes(&fs::read(path)?)
+    }
+
+    /// Writes the checkpoint to `path`, replacing any earlier one in one go so that a render
+    /// stopped while writing still leaves a whole checkpoint behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        write_atomically(path, self.to_bytes())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.scene_hash.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.samples_per_pixel as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.film.width() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.film.height() as u32).to_le_bytes());
+        for pixel in &self.film.pixels {
+            let (count, mean, m2) = pixel.estimator.parts();
+            let values = [
+                pixel.color_sum.x(),
+                pixel.color_sum.y(),
+                pixel.color_sum.z(),
+                pixel.weight_sum,
+            ];
+            for value in values {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&(count as u64).to_le_bytes());
+            bytes.extend_from_slice(&mean.to_le_bytes());
+            bytes.extend_from_slice(&m2.to_le_bytes());
+            bytes.push(pixel.converged as u8);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid_data("not a checkpoint".to_string()));
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "unsupported checkpoint version {}, expected {}",
+                version, VERSION
+            )));
+        }
+        let scene_hash = reader.u64()?;
+        let seed = reader.u64()?;
+        let samples_per_pixel = reader.u64()? as usize;
+        let (width, height) = (reader.u32()?, reader.u32()?);
+        if width == 0 || height == 0 || width > i32::MAX as u32 || height > i32::MAX as u32 {
+            return Err(invalid_data(format!(
+                "invalid film size {}x{}",
+                width, height
+            )));
+        }
+
+        // check the pixels are all there before allocating for them, so a corrupt header can't
+        // ask for more memory than the file could fill
+        let expected_len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(PIXEL_BYTES));
+        if expected_len != Some(reader.bytes.len()) {
+            return Err(invalid_data(format!(
+                "checkpoint has {} bytes of pixels, but a {}x{} film needs {}",
+                reader.bytes.len(),
+                width,
+                height,
+                expected_len.map_or("more".to_string(), |len| len.to_string())
+            )));
+        }
+
+        let mut film = Film::new(width as i32, height as i32);
+        for pixel in &mut film.pixels {
+            let color_sum = Color::new(reader.f64()?, reader.f64()?, reader.f64()?);
+            let weight_sum = reader.f64()?;
+            let (count, mean, m2) = (reader.u64()? as usize, reader.f64()?, reader.f64()?);
+            let converged = reader.take(1)?[0] != 0;
+            *pixel = FilmPixel {
+                color_sum,
+                weight_sum,
+                estimator: VarianceEstimator::from_parts(count, mean, m2),
+                converged,
+            };
+        }
+        Ok(Checkpoint {
+            scene_hash,
+            seed,
+            samples_per_pixel,
+            film,
+        })
+    }
+}
+
+/// Reads little endian values from the front of a slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < length {
+            return Err(invalid_data("checkpoint is truncated".to_string()));
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut film = Film::new(3, 2);
+        film.pixels[4].color_sum = Color::new(1.5, 0.25, -2.0);
+        film.pixels[4].weight_sum = 3.0;
+        film.pixels[4].estimator.add(0.5);
+        film.pixels[4].estimator.add(1.25);
+        film.pixels[4].converged = true;
+        let checkpoint = Checkpoint {
+            scene_hash: 0x0123456789abcdef,
+            seed: 42,
+            samples_per_pixel: 64,
+            film,
+        };
+
+        let bytes = checkpoint.to_bytes();
+        let loaded = Checkpoint::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.scene_hash, checkpoint.scene_hash);
+        assert_eq!((loaded.seed, loaded.samples_per_pixel), (42, 64));
+        assert_eq!(loaded.film.to_ppm(), checkpoint.film.to_ppm());
+        let pixel = &loaded.film.pixels[4];
+        assert_eq!(pixel.estimator.parts(), (2, 0.875, 0.28125));
+        assert!(pixel.converged);
+
+        assert!(Checkpoint::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut newer = bytes.clone();
+        newer[8] = 2;
+        assert!(Checkpoint::from_bytes(&newer).is_err());
+    }
+
+    fn header(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Checkpoint {
+            scene_hash: 0,
+            seed: 0,
+            samples_per_pixel: 1,
+            film: Film::new(1, 1),
+        }
+        .to_bytes();
+        bytes.truncate(bytes.len() - PIXEL_BYTES - 8);
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_rejects_oversized_header() {
+        // a film far too big to allocate, with hardly any pixel data behind it
+        let mut bytes = header(i32::MAX as u32, i32::MAX as u32);
+        bytes.extend_from_slice(&[0; PIXEL_BYTES]);
+        let error = Checkpoint::from_bytes(&bytes).err().unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+
+    #[test]
+    fn test_rejects_truncated_header_and_pixels() {
+        let bytes = header(2, 2);
+        for len in 0..bytes.len() {
+            let error = Checkpoint::from_bytes(&bytes[..len]).err().unwrap();
+            assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        }
+
+        let mut bytes = bytes;
+        bytes.extend_from_slice(&[0; 4 * PIXEL_BYTES]);
+        assert!(Checkpoint::from_bytes(&bytes).is_ok());
+        for len in [bytes.len() - 1, bytes.len() - PIXEL_BYTES] {
+            let error = Checkpoint::from_bytes(&bytes[..len]).err().unwrap();
+            assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        }
+        bytes.push(0);
+        assert!(Checkpoint::from_bytes(&bytes).is_err());
+    }
+}
@@ -1,3 +1,8 @@
 use crate::vec3::Vec3;
 
 pub type Color = Vec3;
+
+/// The perceived brightness of a linear Rec. 709 color.
+pub fn luminance(color: &Color) -> f64 {
+    0.2126 * color.x() + 0.7152 * color.y() + 0.0722 * color.z()
+}
@@ -0,0 +1,154 @@
+use std::io;
+
+use crate::{
+    aabb::Aabb,
+    color::Color,
+    description::Description,
+    hit_record::HitRecord,
+    hittable::Hittable,
+    interval::Interval,
+    materials::{isotropic::Isotropic, material::Material},
+    ray::Ray,
+    vec3::Vec3,
+};
+
+/// A participating medium of uniform density, such as fog or smoke, filling the volume enclosed
+/// by `boundary`. Rays passing through it scatter at an exponentially distributed distance, so
+/// the denser the medium the sooner they do.
+///
+/// The boundary must be closed and convex: a ray is assumed to enter and leave it at most once.
+pub struct ConstantMedium<'a> {
+    boundary: &'a dyn Hittable,
+    density: f64,
+    neg_inv_density: f64,
+    phase_function: Isotropic,
+}
+
+impl<'a> ConstantMedium<'a> {
+    /// Returns an `InvalidInput` error unless `density` is finite and positive.
+    pub fn new(boundary: &'a dyn Hittable, density: f64, albedo: Color) -> io::Result<Self> {
+        if !(density.is_finite() && density > 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("medium density {} isn't finite and positive", density),
+            ));
+        }
+        Ok(ConstantMedium {
+            boundary,
+            density,
+            neg_inv_density: -1.0 / density,
+            phase_function: Isotropic::new(albedo),
+        })
+    }
+}
+
+/// The parametric interval of `ray_t` that lies within a medium's closed, convex `boundary`.
+pub fn inside_boundary(boundary: &dyn Hittable, ray: &Ray, ray_t: &Interval) -> Option<Interval> {
+    // find where the ray's line enters and leaves the boundary, even if the ray itself starts
+    // inside it
+    let entry = boundary.hit(ray, Interval::universe())?;
+    let exit = boundary.hit(ray, Interval::new(entry.t + 0.0001, f64::INFINITY))?;
+
+    let inside = Interval::new(entry.t.max(ray_t.min).max(0.0), exit.t.min(ray_t.max));
+    if inside.size() <= 0.0 {
+        return None;
+    }
+    Some(inside)
+}
+
+impl<'a> Hittable for ConstantMedium<'a> {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord<'_>> {
+        let inside = inside_boundary(self.boundary, ray, &ray_t)?;
+        let ray_length = ray.direction.len();
+        let distance_inside_boundary = inside.size() * ray_length;
+        let hit_distance = self.neg_inv_density * (1.0 - ray.rng(inside.min, 0).next_f64()).ln();
+        if hit_distance > distance_inside_boundary {
+            return None; // the ray made it through without scattering
+        }
+
+        let t = inside.min + hit_distance / ray_length;
+        // a medium has no surface, so the normal and face are arbitrary
+        let normal = Vec3::new(1.0, 0.0, 0.0);
+        Some(HitRecord::new(
+            ray.at(t),
+            normal,
+            t,
+            true,
+            &self.phase_function,
+        ))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    /// Beer-Lambert law: transmittance falls off exponentially with the distance travelled.
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        match inside_boundary(self.boundary, ray, &ray_t) {
+            Some(inside) => (-self.density * inside.size() * ray.direction.len()).exp(),
+            None => 1.0,
+        }
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("constant medium").f64(self.density);
+        self.phase_function.describe(description);
+        self.boundary.describe(description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::lambertian::Lambertian, point::Point, sphere::Sphere};
+
+    #[test]
+    fn test_ray_starting_inside() {
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let boundary = Sphere::new(Point::new(0.0, 0.0, 0.0), 2.0, &grey);
+        let density = 0.5;
+        let medium = ConstantMedium::new(&boundary, density, Color::new(1.0, 1.0, 1.0)).unwrap();
+        // the direction isn't unit length, so distances are twice the ray parameter
+        let mut ray = Ray::new(Point::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 2.0), 0.0);
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        // the medium starts at the ray's origin, not where its line enters the boundary behind it
+        let length = 2.0 - 0.002;
+
+        let transmittance = medium.transmittance(&ray, ray_t.clone());
+        assert!((transmittance - (-density * length).exp()).abs() < 1e-12);
+
+        let n = 100_000;
+        let mut scattered = 0;
+        let mut distance_sum = 0.0;
+        for i in 0..n {
+            ray.sample_seed = i;
+            if let Some(hit) = medium.hit(&ray, ray_t.clone()) {
+                let distance = (hit.t - 0.001) * 2.0;
+                assert!((0.0..=length).contains(&distance));
+                assert_eq!(ray.at(hit.t), hit.point);
+                scattered += 1;
+                distance_sum += distance;
+            }
+        }
+
+        // rays scatter as often as they aren't transmitted, at exponentially distributed
+        // distances cut off at the boundary
+        let scattered_fraction = scattered as f64 / n as f64;
+        let mean_distance = distance_sum / scattered as f64;
+        let falloff = (-density * length).exp();
+        let expected_mean = 1.0 / density - length * falloff / (1.0 - falloff);
+        assert!((scattered_fraction - (1.0 - transmittance)).abs() < 1e-2);
+        assert!((mean_distance - expected_mean).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_rejects_invalid_densities() {
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let boundary = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0, &grey);
+        let white = Color::new(1.0, 1.0, 1.0);
+        for density in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            let error = ConstantMedium::new(&boundary, density, white.clone()).err();
+            assert_eq!(io::ErrorKind::InvalidInput, error.unwrap().kind());
+        }
+    }
+}
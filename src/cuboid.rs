@@ -0,0 +1,177 @@
+use crate::{
+    aabb::Aabb, description::Description, hit_record::HitRecord, hittable::Hittable,
+    interval::Interval, materials::material::Material, point::Point, quad::Quad, ray::Ray,
+    vec3::Vec3,
+};
+
+/// An axis-aligned box made up of six quads, spanning the two opposite corners `a` and `b`.
+pub struct Cuboid<'a> {
+    sides: [Quad<'a>; 6],
+    /// The area of each side, which is sampled in proportion to it.
+    areas: [f64; 6],
+    area: f64,
+}
+
+impl<'a> Cuboid<'a> {
+    pub fn new(a: Point, b: Point, material: &'a dyn Material) -> Self {
+        let min = Point::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+        let max = Point::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+
+        let dx = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y() - min.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z() - min.z());
+        let front = dx.x() * dy.y();
+        let side = dz.z() * dy.y();
+        let top = dx.x() * dz.z();
+        let areas = [front, side, front, side, top, top];
+
+        // each side's edges are ordered so that its normal faces outwards
+        let sides = [
+            Quad::new(
+                Point::new(min.x(), min.y(), max.z()),
+                dx.clone(),
+                dy.clone(),
+                material,
+            ), // front
+            Quad::new(
+                Point::new(max.x(), min.y(), max.z()),
+                -dz.clone(),
+                dy.clone(),
+                material,
+            ), // right
+            Quad::new(
+                Point::new(max.x(), min.y(), min.z()),
+                -dx.clone(),
+                dy.clone(),
+                material,
+            ), // back
+            Quad::new(
+                Point::new(min.x(), min.y(), min.z()),
+                dz.clone(),
+                dy,
+                material,
+            ), // left
+            Quad::new(
+                Point::new(min.x(), max.y(), max.z()),
+                dx.clone(),
+                -dz.clone(),
+                material,
+            ), // top
+            Quad::new(Point::new(min.x(), min.y(), min.z()), dx, dz, material), // bottom
+        ];
+        Cuboid {
+            sides,
+            areas,
+            area: areas.iter().sum(),
+        }
+    }
+}
+
+impl<'a> Hittable for Cuboid<'a> {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord<'_>> {
+        let mut curr_closest = ray_t.max;
+        let mut hit_record = None;
+        for side in &self.sides {
+            if let Some(hit) = side.hit(ray, Interval::new(ray_t.min, curr_closest)) {
+                curr_closest = hit.t;
+                hit_record = Some(hit);
+            }
+        }
+        hit_record
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.sides.iter().fold(Aabb::empty(), |bbox, side| {
+            Aabb::enclosing(&bbox, &side.bounding_box())
+        })
+    }
+
+    /// Points are sampled uniformly over the whole surface, so a direction may have been sampled
+    /// through any of the sides it crosses, even those hidden behind the nearest one.
+    fn pdf_value(&self, origin: &Point, direction: &Vec3) -> f64 {
+        if self.area <= 0.0 {
+            return 0.0;
+        }
+        self.sides
+            .iter()
+            .zip(self.areas)
+            .map(|(side, area)| area / self.area * side.pdf_value(origin, direction))
+            .sum()
+    }
+
+    fn random(&self, origin: &Point, u: [f64; 2]) -> Vec3 {
+        // pick a side by its area, then rescale `u[0]` so it can be reused within the side
+        let mut u0 = u[0] * self.area;
+        for (side, area) in self.sides.iter().zip(self.areas) {
+            if u0 < area {
+                return side.random(origin, [u0 / area, u[1]]);
+            }
+            u0 -= area;
+        }
+        self.sides[5].random(origin, [1.0, u[1]])
+    }
+
+    fn power(&self) -> f64 {
+        self.sides.iter().map(|side| side.power()).sum()
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("cuboid");
+        for side in &self.sides {
+            side.describe(description);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{color::Color, materials::diffuse_light::DiffuseLight};
+
+    #[test]
+    fn test_pdf_normalised_and_covers_samples() {
+        let light = DiffuseLight::new(Color::new(1.0, 1.0, 1.0));
+        let cuboid = Cuboid::new(
+            Point::new(-1.0, -0.5, -2.0),
+            Point::new(1.0, 0.5, -1.0),
+            &light,
+        );
+        let origin = Point::new(0.3, 1.2, 0.5);
+
+        // integrate the pdf over the sphere of directions
+        let (n_theta, n_phi) = (800, 800);
+        let d_theta = PI / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut total = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let direction = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                total += cuboid.pdf_value(&origin, &direction) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        assert!((total - 1.0).abs() < 1e-2, "pdf integrates to {total}");
+
+        // sampled points lie on the surface
+        let min = [-1.0, -0.5, -2.0];
+        let max = [1.0, 0.5, -1.0];
+        let n = 60;
+        for i in 0..n {
+            let u = [(i as f64 + 0.5) / n as f64, 0.37];
+            let direction = cuboid.random(&origin, u);
+            let point = &origin + &direction;
+            let on_surface = (0..3).any(|axis| {
+                (point[axis] - min[axis]).abs() < 1e-9 || (point[axis] - max[axis]).abs() < 1e-9
+            });
+            assert!(on_surface);
+            assert!(cuboid.pdf_value(&origin, &direction) > 0.0);
+        }
+    }
+}
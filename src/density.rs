@@ -0,0 +1,292 @@
+use std::{fs, io, path::Path};
+
+use crate::{description::Description, perlin::Perlin, point::Point};
+
+/// A density that varies through space, defining a heterogeneous medium.
+pub trait DensityField {
+    /// The extinction coefficient at `p`, i.e. the probability per unit distance of a ray
+    /// interacting with the medium there.
+    fn density(&self, p: &Point) -> f64;
+
+    /// An upper bound on `density` everywhere, used as the majorant when tracking rays.
+    fn max_density(&self) -> f64;
+
+    /// Adds the kind of field and its parameters to `description`.
+    fn describe(&self, description: &mut Description);
+}
+
+/// Density driven by Perlin turbulence, giving billowing cloud and smoke shapes.
+pub struct NoiseDensity {
+    noise: Perlin,
+    frequency: f64,
+    octaves: u32,
+    scale: f64,
+}
+
+impl NoiseDensity {
+    /// `frequency` sets how many noise features fit in a unit of distance, `octaves` how much fine
+    /// detail is layered on top, and `scale` the density of the thickest regions.
+    pub fn new(seed: u64, frequency: f64, octaves: u32, scale: f64) -> Self {
+        NoiseDensity {
+            noise: Perlin::new(seed),
+            frequency,
+            octaves,
+            scale,
+        }
+    }
+}
+
+impl DensityField for NoiseDensity {
+    fn density(&self, p: &Point) -> f64 {
+        let turbulence = self.noise.turbulence(&(p * self.frequency), self.octaves);
+        self.scale * turbulence.min(1.0)
+    }
+
+    fn max_density(&self) -> f64 {
+        self.scale
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("noise density")
+            .f64(self.frequency)
+            .u64(self.octaves as u64)
+            .f64(self.scale);
+        self.noise.describe(description);
+    }
+}
+
+/// Density sampled on a regular voxel grid spanning an axis-aligned box, and trilinearly
+/// interpolated between voxel centres. Outside of the box, the density is 0.
+pub struct GridDensity {
+    values: Vec<f64>,
+    dims: [usize; 3],
+    min: Point,
+    max: Point,
+    max_density: f64,
+}
+
+impl GridDensity {
+    /// `values` are ordered with x varying fastest, then y, then z. Returns an `InvalidInput`
+    /// error if any dimension is 0, the number of values doesn't match them, or a value isn't a
+    /// finite, non-negative density.
+    pub fn new(values: Vec<f64>, dims: [usize; 3], min: Point, max: Point) -> io::Result<Self> {
+        let count = voxel_count(dims).map_err(invalid_input)?;
+        if values.len() != count {
+            return Err(invalid_input(format!(
+                "expected {} values for a {}x{}x{} grid, found {}",
+                count,
+                dims[0],
+                dims[1],
+                dims[2],
+                values.len()
+            )));
+        }
+        check_values(&values).map_err(invalid_input)?;
+        let max_density = values.iter().cloned().fold(0.0, f64::max);
+        Ok(GridDensity {
+            values,
+            dims,
+            min,
+            max,
+            max_density,
+        })
+    }
+
+    /// Loads a grid from a headerless file of little-endian `f32`s, ordered as in `new`. Returns
+    /// an `InvalidData` error if the file doesn't hold a valid grid of size `dims`.
+    pub fn from_raw_file(
+        path: impl AsRef<Path>,
+        dims: [usize; 3],
+        min: Point,
+        max: Point,
+    ) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let expected_len = voxel_count(dims)
+            .map_err(invalid_data)?
+            .checked_mul(4)
+            .ok_or_else(|| invalid_data("grid is too large".to_string()))?;
+        if bytes.len() != expected_len {
+            return Err(invalid_data(format!(
+                "expected {} bytes for a {}x{}x{} grid, found {}",
+                expected_len,
+                dims[0],
+                dims[1],
+                dims[2],
+                bytes.len()
+            )));
+        }
+
+        let values: Vec<f64> = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64)
+            .collect();
+        check_values(&values).map_err(invalid_data)?;
+        GridDensity::new(values, dims, min, max)
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.values[(z * self.dims[1] + y) * self.dims[0] + x]
+    }
+}
+
+impl DensityField for GridDensity {
+    fn density(&self, p: &Point) -> f64 {
+        // continuous grid coordinates, where voxel centres fall on whole numbers
+        let mut coords = [0.0; 3];
+        for axis in 0..3 {
+            let extent = self.max[axis] - self.min[axis];
+            let t = (p[axis] - self.min[axis]) / extent;
+            if !(0.0..=1.0).contains(&t) {
+                return 0.0;
+            }
+            coords[axis] = t * self.dims[axis] as f64 - 0.5;
+        }
+
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut frac = [0.0; 3];
+        for axis in 0..3 {
+            let last = self.dims[axis] - 1;
+            let c = coords[axis].clamp(0.0, last as f64);
+            lower[axis] = c.floor() as usize;
+            upper[axis] = (lower[axis] + 1).min(last);
+            frac[axis] = c - lower[axis] as f64;
+        }
+
+        let mut density = 0.0;
+        for (dx, x) in [(1.0 - frac[0], lower[0]), (frac[0], upper[0])] {
+            for (dy, y) in [(1.0 - frac[1], lower[1]), (frac[1], upper[1])] {
+                for (dz, z) in [(1.0 - frac[2], lower[2]), (frac[2], upper[2])] {
+                    density += dx * dy * dz * self.voxel(x, y, z);
+                }
+            }
+        }
+        density
+    }
+
+    fn max_density(&self) -> f64 {
+        self.max_density
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("grid density");
+        for dim in self.dims {
+            description.u64(dim as u64);
+        }
+        description
+            .vec3(&self.min)
+            .vec3(&self.max)
+            .f64s(&self.values);
+    }
+}
+
+/// The number of voxels in a grid of size `dims`, or why there can't be such a grid.
+fn voxel_count(dims: [usize; 3]) -> Result<usize, String> {
+    if dims.contains(&0) {
+        return Err(format!(
+            "grid dimensions must be positive, found {}x{}x{}",
+            dims[0], dims[1], dims[2]
+        ));
+    }
+    dims[0]
+        .checked_mul(dims[1])
+        .and_then(|count| count.checked_mul(dims[2]))
+        .ok_or_else(|| "grid is too large".to_string())
+}
+
+/// Checks that every value is a finite, non-negative density.
+fn check_values(values: &[f64]) -> Result<(), String> {
+    match values.iter().find(|v| !v.is_finite() || **v < 0.0) {
+        Some(value) => Err(format!("invalid density: {}", value)),
+        None => Ok(()),
+    }
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn two_voxel_grid() -> GridDensity {
+        GridDensity::new(
+            vec![1.0, 3.0],
+            [2, 1, 1],
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(2.0, 1.0, 1.0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_grid_density_at_voxel_centres() {
+        let grid = two_voxel_grid();
+
+        assert_eq!(1.0, grid.density(&Point::new(0.5, 0.5, 0.5)));
+        assert_eq!(3.0, grid.density(&Point::new(1.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn test_grid_density_interpolates() {
+        let grid = two_voxel_grid();
+
+        assert_eq!(2.0, grid.density(&Point::new(1.0, 0.2, 0.9)));
+    }
+
+    #[test]
+    fn test_grid_density_outside() {
+        let grid = two_voxel_grid();
+
+        assert_eq!(0.0, grid.density(&Point::new(-0.1, 0.5, 0.5)));
+        assert_eq!(3.0, grid.max_density());
+    }
+
+    #[test]
+    fn test_grid_from_raw_file() {
+        let path = std::env::temp_dir().join("ray_tracing_test_grid.raw");
+        let bytes: Vec<u8> = [1.0f32, 3.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        fs::write(&path, bytes).unwrap();
+        let min = Point::new(0.0, 0.0, 0.0);
+        let max = Point::new(2.0, 1.0, 1.0);
+
+        let grid = GridDensity::from_raw_file(&path, [2, 1, 1], min.clone(), max.clone()).unwrap();
+        assert_eq!(3.0, grid.density(&Point::new(1.5, 0.5, 0.5)));
+
+        let res = GridDensity::from_raw_file(&path, [2, 2, 1], min.clone(), max.clone());
+        assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        let res = GridDensity::from_raw_file(&path, [2, 0, 1], min.clone(), max.clone());
+        assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        let res = GridDensity::from_raw_file(&path, [usize::MAX, 2, 1], min.clone(), max.clone());
+        assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+
+        for bad in [f32::NAN, f32::INFINITY, -1.0] {
+            let bytes: Vec<u8> = [1.0f32, bad].iter().flat_map(|v| v.to_le_bytes()).collect();
+            fs::write(&path, bytes).unwrap();
+            let res = GridDensity::from_raw_file(&path, [2, 1, 1], min.clone(), max.clone());
+            assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_grid_rejects_invalid_input() {
+        let min = Point::new(0.0, 0.0, 0.0);
+        let max = Point::new(1.0, 1.0, 1.0);
+        for (values, dims) in [
+            (vec![], [0, 1, 1]),
+            (vec![1.0], [1, 1, 2]),
+            (vec![f64::NAN], [1, 1, 1]),
+            (vec![-0.5], [1, 1, 1]),
+        ] {
+            let res = GridDensity::new(values, dims, min.clone(), max.clone());
+            assert_eq!(io::ErrorKind::InvalidInput, res.err().unwrap().kind());
+        }
+    }
+}
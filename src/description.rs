@@ -0,0 +1,91 @@
+use crate::{
+    pcg::{hash_combine, HASH_START},
+    vec3::Vec3,
+};
+
+/// A hash of how something was built: what kind of thing it is and every parameter that affects
+/// how it renders, along with everything it's built from. Scenes are built in code rather than
+/// loaded from a file, so this is how a render tells whether the scene it's resuming is the one
+/// it was checkpointed with.
+///
+/// Each kind of thing starts with its `kind`, so that different kinds with the same parameters
+/// don't describe themselves the same way.
+#[derive(Debug, Clone)]
+pub struct Description {
+    hash: u64,
+}
+
+impl Description {
+    pub fn new() -> Self {
+        Description { hash: HASH_START }
+    }
+
+    pub fn kind(&mut self, name: &str) -> &mut Self {
+        self.u64(name.len() as u64);
+        for byte in name.bytes() {
+            self.u64(byte as u64);
+        }
+        self
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.hash = hash_combine(self.hash, value);
+        self
+    }
+
+    pub fn f64(&mut self, value: f64) -> &mut Self {
+        self.u64(value.to_bits())
+    }
+
+    pub fn vec3(&mut self, v: &Vec3) -> &mut Self {
+        self.f64(v.x()).f64(v.y()).f64(v.z())
+    }
+
+    /// Adds a list of values along with its length, so that where one list ends and what follows
+    /// it begins can't be confused.
+    pub fn f64s(&mut self, values: &[f64]) -> &mut Self {
+        self.u64(values.len() as u64);
+        for value in values {
+            self.f64(*value);
+        }
+        self
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Default for Description {
+    fn default() -> Self {
+        Description::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcg::hash;
+
+    #[test]
+    fn test_hash_matches_values_hashed_at_once() {
+        let mut description = Description::new();
+        description.u64(3).f64(0.5);
+        assert_eq!(hash(&[3, 0.5f64.to_bits()]), description.hash());
+    }
+
+    #[test]
+    fn test_kinds_and_lists_are_kept_apart() {
+        let mut box_filter = Description::new();
+        box_filter.kind("box").f64(0.5);
+        let mut tent_filter = Description::new();
+        tent_filter.kind("tent").f64(0.5);
+        assert_ne!(box_filter.hash(), tent_filter.hash());
+
+        let mut pair_then_none = Description::new();
+        pair_then_none.f64s(&[1.0, 2.0]).f64s(&[]);
+        let mut one_then_one = Description::new();
+        one_then_one.f64s(&[1.0]).f64s(&[2.0]);
+        assert_ne!(pair_then_none.hash(), one_then_one.hash());
+    }
+}
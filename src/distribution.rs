@@ -0,0 +1,323 @@
+use crate::description::Description;
+
+/// A piecewise-constant 1D distribution over `[0, 1)`, proportional to a tabulated function, that
+/// can be sampled by inverting its CDF.
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    /// `func` must be non-negative. If it is zero everywhere, the distribution is uniform. Panics
+    /// if `func` is empty, as there would be nothing to sample.
+    pub fn new(func: &[f64]) -> Self {
+        assert!(!func.is_empty(), "distribution must not be empty");
+        let n = func.len();
+        let mut cdf = vec![0.0; n + 1];
+        for i in 0..n {
+            cdf[i + 1] = cdf[i] + func[i].abs() / n as f64;
+        }
+        let integral = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate().skip(1) {
+            *c = if integral > 0.0 {
+                *c / integral
+            } else {
+                i as f64 / n as f64
+            };
+        }
+        Distribution1D {
+            func: func.iter().map(|f| f.abs()).collect(),
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// The integral of the tabulated function over `[0, 1)`.
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Maps a uniform random number to a point in `[0, 1)`, returning the point, its density and
+    /// the index of the segment it fell in.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        // find the last segment whose cdf starts at or below u
+        let offset = self
+            .cdf
+            .partition_point(|c| *c <= u)
+            .saturating_sub(1)
+            .min(self.count() - 1);
+
+        let segment = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if segment > 0.0 {
+            (u - self.cdf[offset]) / segment
+        } else {
+            0.0
+        };
+        let x = (offset as f64 + du) / self.count() as f64;
+        (x, self.pdf_at(offset), offset)
+    }
+
+    /// The density of sampling any point within segment `offset`.
+    pub fn pdf_at(&self, offset: usize) -> f64 {
+        if self.integral > 0.0 {
+            self.func[offset] / self.integral
+        } else {
+            1.0
+        }
+    }
+
+    /// The density of sampling `x`.
+    pub fn pdf(&self, x: f64) -> f64 {
+        let offset = ((x * self.count() as f64) as usize).min(self.count() - 1);
+        self.pdf_at(offset)
+    }
+
+    /// Adds the tabulated function to `description`.
+    pub fn describe(&self, description: &mut Description) {
+        description.f64s(&self.func);
+    }
+}
+
+/// A piecewise-constant 2D distribution over `[0, 1)^2`, sampled by first choosing a row from the
+/// marginal distribution and then a column from that row's conditional distribution.
+pub struct Distribution2D {
+    conditionals: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    /// `func` holds `width * height` values, row by row. Panics if either dimension is 0 or the
+    /// number of values doesn't match them.
+    pub fn new(func: &[f64], width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "distribution must not be empty");
+        assert_eq!(
+            Some(func.len()),
+            width.checked_mul(height),
+            "value count must match dimensions"
+        );
+        let conditionals: Vec<Distribution1D> = func
+            .chunks_exact(width)
+            .take(height)
+            .map(Distribution1D::new)
+            .collect();
+        let row_integrals: Vec<f64> = conditionals.iter().map(|c| c.integral()).collect();
+        Distribution2D {
+            marginal: Distribution1D::new(&row_integrals),
+            conditionals,
+        }
+    }
+
+    /// Maps a pair of uniform random numbers to a point `(x, y)` in `[0, 1)^2`, returning it and
+    /// its density.
+    pub fn sample(&self, u: [f64; 2]) -> ([f64; 2], f64) {
+        let (y, pdf_y, row) = self.marginal.sample(u[1]);
+        let (x, pdf_x, _) = self.conditionals[row].sample(u[0]);
+        ([x, y], pdf_x * pdf_y)
+    }
+
+    /// The density of sampling the point `(x, y)`.
+    pub fn pdf(&self, p: [f64; 2]) -> f64 {
+        let row = ((p[1] * self.marginal.count() as f64) as usize).min(self.marginal.count() - 1);
+        self.conditionals[row].pdf(p[0]) * self.marginal.pdf_at(row)
+    }
+
+    /// Adds the tabulated function to `description`, row by row.
+    pub fn describe(&self, description: &mut Description) {
+        description.u64(self.conditionals.len() as u64);
+        for conditional in &self.conditionals {
+            conditional.describe(description);
+        }
+    }
+}
+
+/// Samples from a discrete distribution in constant time, using Walker's alias method as
+/// constructed by Vose's "A Linear Algorithm for Generating Random Numbers with a Given
+/// Distribution".
+pub struct AliasTable {
+    bins: Vec<AliasBin>,
+}
+
+struct AliasBin {
+    /// The probability of keeping the bin's own index rather than taking its alias.
+    q: f64,
+    alias: usize,
+    /// The probability of sampling the bin's index overall.
+    pmf: f64,
+}
+
+impl AliasTable {
+    /// `weights` must be non-negative. If they are all zero, every index is equally likely. Panics
+    /// if `weights` is empty, as there would be nothing to sample.
+    pub fn new(weights: &[f64]) -> Self {
+        assert!(!weights.is_empty(), "alias table must not be empty");
+        let n = weights.len();
+        let total: f64 = weights.iter().sum();
+        let pmfs: Vec<f64> = if total > 0.0 {
+            weights.iter().map(|w| w / total).collect()
+        } else {
+            vec![1.0 / n as f64; n]
+        };
+
+        let mut bins: Vec<AliasBin> = pmfs
+            .iter()
+            .map(|pmf| AliasBin {
+                q: 0.0,
+                alias: 0,
+                pmf: *pmf,
+            })
+            .collect();
+
+        // split the bins into those with less and more than their fair share, then repeatedly
+        // top up an under-full bin with the excess of an over-full one
+        let mut under = Vec::new();
+        let mut over = Vec::new();
+        for (i, pmf) in pmfs.iter().enumerate() {
+            let scaled = pmf * n as f64;
+            if scaled < 1.0 {
+                under.push((i, scaled));
+            } else {
+                over.push((i, scaled));
+            }
+        }
+        while !under.is_empty() && !over.is_empty() {
+            let (small, small_q) = under.pop().unwrap();
+            let (large, large_q) = over.pop().unwrap();
+            bins[small].q = small_q;
+            bins[small].alias = large;
+
+            let excess = large_q - (1.0 - small_q);
+            if excess < 1.0 {
+                under.push((large, excess));
+            } else {
+                over.push((large, excess));
+            }
+        }
+        // whatever is left over is full, up to rounding error
+        for (i, _) in under.into_iter().chain(over) {
+            bins[i].q = 1.0;
+            bins[i].alias = i;
+        }
+
+        AliasTable { bins }
+    }
+
+    pub fn count(&self) -> usize {
+        self.bins.len()
+    }
+
+    /// Maps a uniform random number to an index, returning it and its probability.
+    pub fn sample(&self, u: f64) -> (usize, f64) {
+        let scaled = u * self.count() as f64;
+        let offset = (scaled as usize).min(self.count() - 1);
+        let up = (scaled - offset as f64).min(1.0);
+        let index = if up < self.bins[offset].q {
+            offset
+        } else {
+            self.bins[offset].alias
+        };
+        (index, self.bins[index].pmf)
+    }
+
+    /// The probability of sampling `index`.
+    pub fn pmf(&self, index: usize) -> f64 {
+        self.bins[index].pmf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distribution_1d_sample() {
+        let distribution = Distribution1D::new(&[1.0, 3.0]);
+
+        let (x, pdf, offset) = distribution.sample(0.5);
+        assert_eq!(1, offset);
+        assert!((x - (0.5 + 0.5 * (0.25 / 0.75))).abs() < 1e-12);
+        assert!((pdf - 1.5).abs() < 1e-12);
+
+        let (x, pdf, offset) = distribution.sample(0.125);
+        assert_eq!(0, offset);
+        assert!((x - 0.25).abs() < 1e-12);
+        assert!((pdf - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_distribution_1d_skips_empty_segments() {
+        let distribution = Distribution1D::new(&[0.0, 2.0, 0.0]);
+
+        for u in [0.0, 0.3, 0.999] {
+            let (x, _, offset) = distribution.sample(u);
+            assert_eq!(1, offset);
+            assert!((1.0 / 3.0..2.0 / 3.0).contains(&x));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "distribution must not be empty")]
+    fn test_distribution_1d_rejects_empty() {
+        Distribution1D::new(&[]);
+    }
+
+    #[test]
+    #[should_panic(expected = "distribution must not be empty")]
+    fn test_distribution_2d_rejects_empty() {
+        Distribution2D::new(&[], 0, 3);
+    }
+
+    #[test]
+    fn test_distribution_2d_pdf_matches_sample() {
+        let func = [1.0, 2.0, 3.0, 0.0, 5.0, 1.0];
+        let distribution = Distribution2D::new(&func, 3, 2);
+
+        // a multiple of both dimensions, so the midpoint rule is exact
+        let n = 60;
+        let mut integral = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let u = [(i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64];
+                let (p, pdf) = distribution.sample(u);
+                assert!((pdf - distribution.pdf(p)).abs() < 1e-12);
+                integral += distribution.pdf(u) / (n * n) as f64;
+            }
+        }
+        assert!((integral - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_alias_table_frequencies() {
+        let weights = [1.0, 0.0, 5.0, 2.0, 2.0];
+        let table = AliasTable::new(&weights);
+
+        let n = 10000;
+        let mut counts = [0; 5];
+        for i in 0..n {
+            let (index, pmf) = table.sample((i as f64 + 0.5) / n as f64);
+            assert_eq!(weights[index] / 10.0, pmf);
+            counts[index] += 1;
+        }
+        for (count, weight) in counts.iter().zip(weights) {
+            assert!((*count as f64 / n as f64 - weight / 10.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_alias_table_uniform_without_weight() {
+        let table = AliasTable::new(&[0.0, 0.0, 0.0, 0.0]);
+        for i in 0..4 {
+            assert_eq!((i, 0.25), table.sample((i as f64 + 0.5) / 4.0));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "alias table must not be empty")]
+    fn test_alias_table_rejects_empty() {
+        AliasTable::new(&[]);
+    }
+}
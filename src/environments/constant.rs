@@ -0,0 +1,43 @@
+use crate::{
+    color::Color,
+    description::Description,
+    pdf::{Pdf, SpherePdf},
+    vec3::Vec3,
+};
+
+use super::environment::Environment;
+
+/// The same radiance from every direction.
+pub struct Constant {
+    radiance: Color,
+}
+
+impl Constant {
+    pub fn new(radiance: Color) -> Self {
+        Constant { radiance }
+    }
+}
+
+impl Environment for Constant {
+    fn radiance(&self, _: &Vec3) -> Color {
+        self.radiance.clone()
+    }
+
+    fn can_sample(&self) -> bool {
+        true
+    }
+
+    fn sample(&self, u: [f64; 2]) -> Option<(Vec3, f64)> {
+        let direction = SpherePdf.generate(u);
+        let pdf = SpherePdf.value(&direction);
+        Some((direction, pdf))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        SpherePdf.value(direction)
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("constant").vec3(&self.radiance);
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{color::Color, description::Description, vec3::Vec3};
+
+/// The light arriving from infinitely far away, seen by rays that escape the scene.
+pub trait Environment {
+    /// The radiance arriving along `direction`, which points away from the scene.
+    fn radiance(&self, direction: &Vec3) -> Color;
+
+    /// Whether `sample` can draw directions, so that the environment can be sampled as a light.
+    fn can_sample(&self) -> bool {
+        false
+    }
+
+    /// Samples a unit direction towards the environment, ideally in proportion to its radiance,
+    /// returning it and its density per unit solid angle.
+    fn sample(&self, _u: [f64; 2]) -> Option<(Vec3, f64)> {
+        None
+    }
+
+    /// The density with which `sample` returns `direction`.
+    fn pdf(&self, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    /// Adds the kind of environment and its parameters to `description`.
+    fn describe(&self, description: &mut Description);
+}
@@ -0,0 +1,39 @@
+use crate::{color::Color, description::Description, vec3::Vec3};
+
+use super::environment::Environment;
+
+/// A vertical blend between two colors, from `bottom` looking straight down to `top` looking
+/// straight up.
+pub struct Gradient {
+    bottom: Color,
+    top: Color,
+}
+
+impl Gradient {
+    pub fn new(bottom: Color, top: Color) -> Self {
+        Gradient { bottom, top }
+    }
+}
+
+impl Environment for Gradient {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let direction_unit = direction.unit();
+        // `a` is a value in the range [0,1] based on the direction_unit's y component
+        let a = 0.5 * (direction_unit.y() + 1.0);
+        lerp(a, &self.bottom, &self.top)
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("gradient")
+            .vec3(&self.bottom)
+            .vec3(&self.top);
+    }
+}
+
+/// Linear blend/linear interpolation/lerp
+/// Takes some value `a` in the range `[0,1]` and start & end values. Calculates the value that
+/// pertains to `a` when placed in the gradient between start & end.
+fn lerp(a: f64, start_value: &Color, end_value: &Color) -> Color {
+    start_value * (1.0 - a) + end_value * a
+}
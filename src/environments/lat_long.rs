@@ -0,0 +1,157 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::{luminance, Color},
+    deg_to_radians,
+    description::Description,
+    distribution::Distribution2D,
+    hdr_image::HdrImage,
+    vec3::Vec3,
+};
+
+use super::environment::Environment;
+
+/// An equirectangular (latitude-longitude) environment map. The top row of the image looks
+/// straight up, and the middle column looks down -z before any rotation is applied.
+pub struct LatLong {
+    image: HdrImage,
+    /// Rotation about the vertical axis, in radians.
+    rotation: f64,
+    intensity: f64,
+    distribution: Distribution2D,
+}
+
+impl LatLong {
+    /// `rotation` turns the map about the vertical axis, in degrees, and `intensity` scales its
+    /// radiance.
+    pub fn new(image: HdrImage, rotation: f64, intensity: f64) -> Self {
+        // sample pixels by their luminance, weighted by the solid angle each row covers, which
+        // shrinks towards the poles
+        let mut func = Vec::with_capacity(image.width * image.height);
+        for y in 0..image.height {
+            let sin_theta = (PI * (y as f64 + 0.5) / image.height as f64).sin();
+            for x in 0..image.width {
+                func.push(luminance(image.pixel(x, y)).max(0.0) * sin_theta);
+            }
+        }
+        let distribution = Distribution2D::new(&func, image.width, image.height);
+
+        LatLong {
+            image,
+            rotation: deg_to_radians(rotation),
+            intensity,
+            distribution,
+        }
+    }
+
+    /// Maps a world space direction onto image coordinates in `[0, 1]^2`.
+    fn direction_to_uv(&self, direction: &Vec3) -> [f64; 2] {
+        let d = direction.unit();
+        let phi = d.x().atan2(-d.z()) - self.rotation;
+        let theta = d.y().clamp(-1.0, 1.0).acos();
+        let u = (0.5 + phi / (2.0 * PI)).rem_euclid(1.0);
+        [u, theta / PI]
+    }
+
+    fn uv_to_direction(&self, uv: [f64; 2]) -> Vec3 {
+        let phi = (uv[0] - 0.5) * 2.0 * PI + self.rotation;
+        let theta = uv[1] * PI;
+        Vec3::new(
+            theta.sin() * phi.sin(),
+            theta.cos(),
+            -theta.sin() * phi.cos(),
+        )
+    }
+}
+
+impl Environment for LatLong {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let uv = self.direction_to_uv(direction);
+        let x = ((uv[0] * self.image.width as f64) as usize).min(self.image.width - 1);
+        let y = ((uv[1] * self.image.height as f64) as usize).min(self.image.height - 1);
+        self.image.pixel(x, y) * self.intensity
+    }
+
+    fn can_sample(&self) -> bool {
+        true
+    }
+
+    fn sample(&self, u: [f64; 2]) -> Option<(Vec3, f64)> {
+        let (uv, pdf_uv) = self.distribution.sample(u);
+        let sin_theta = (uv[1] * PI).sin();
+        if pdf_uv <= 0.0 || sin_theta <= 0.0 {
+            return None;
+        }
+        // change of variables from the unit square to the sphere
+        let pdf = pdf_uv / (2.0 * PI * PI * sin_theta);
+        Some((self.uv_to_direction(uv), pdf))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        let uv = self.direction_to_uv(direction);
+        let sin_theta = (uv[1] * PI).sin();
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(uv) / (2.0 * PI * PI * sin_theta)
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("lat-long")
+            .f64(self.rotation)
+            .f64(self.intensity);
+        self.image.describe(description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_image() -> HdrImage {
+        let (width, height) = (8, 4);
+        let pixels = (0..width * height)
+            .map(|i| {
+                let v = 0.1 + (i % 5) as f64;
+                Color::new(v, 0.5 * v, 1.0)
+            })
+            .collect();
+        HdrImage::new(width, height, pixels).unwrap()
+    }
+
+    #[test]
+    fn test_lat_long_uv_round_trip() {
+        let map = LatLong::new(test_image(), 30.0, 1.0);
+        let direction = Vec3::new(0.3, -0.4, 0.5).unit();
+        let res = map.uv_to_direction(map.direction_to_uv(&direction));
+
+        assert!((&res - &direction).len() < 1e-12);
+    }
+
+    #[test]
+    fn test_lat_long_sample_matches_pdf() {
+        let map = LatLong::new(test_image(), 45.0, 2.0);
+        let n = 32;
+        for i in 0..n {
+            for j in 0..n {
+                let u = [(i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64];
+                let (direction, pdf) = map.sample(u).unwrap();
+
+                assert!((pdf - map.pdf(&direction)).abs() < 1e-9 * pdf);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lat_long_samples_bright_pixels() {
+        let mut pixels = vec![Color::new(0.0, 0.0, 0.0); 8 * 4];
+        pixels[2 * 8 + 5] = Color::new(10.0, 10.0, 10.0);
+        let map = LatLong::new(HdrImage::new(8, 4, pixels).unwrap(), 90.0, 1.0);
+
+        for u in [[0.1, 0.2], [0.9, 0.5], [0.5, 0.99]] {
+            let (direction, _) = map.sample(u).unwrap();
+            assert_eq!(10.0, map.radiance(&direction).x());
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod constant;
+pub mod environment;
+pub mod gradient;
+pub mod lat_long;
+pub mod sky;
+pub mod sun;
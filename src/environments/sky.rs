@@ -0,0 +1,294 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::{luminance, Color},
+    description::Description,
+    hdr_image::HdrImage,
+    vec3::{dot, Vec3},
+};
+
+use super::{environment::Environment, lat_long::LatLong, sun::Sun};
+
+/// Resolution of the table the sky is importance sampled from.
+const TABLE_WIDTH: usize = 128;
+const TABLE_HEIGHT: usize = 64;
+
+/// A clear daylight sky following Preetham et al. "A Practical Analytic Model for Daylight", with
+/// a sun disk and a uniformly lit ground below the horizon. Radiance is in kcd/m², scaled by
+/// `intensity`, so a clear midday sky needs an intensity of a few hundredths to render sensibly.
+pub struct Sky {
+    perez: Preetham,
+    sun: Sun,
+    ground: Color,
+    intensity: f64,
+    /// The sky and ground without the sun, tabulated to sample directions from.
+    table: LatLong,
+    /// The probability of sampling the sun rather than the table.
+    sun_probability: f64,
+}
+
+impl Sky {
+    /// `turbidity` measures the haziness of the atmosphere, from about 2 for a clear sky to 10
+    /// for a hazy one. `ground_albedo` is the reflectance of the ground, lit by the sky and sun.
+    /// `sun_angular_diameter` is in degrees; the real sun's is about 0.53, and larger values give
+    /// softer shadows.
+    pub fn new(
+        sun_direction: &Vec3,
+        turbidity: f64,
+        ground_albedo: Color,
+        sun_angular_diameter: f64,
+        intensity: f64,
+    ) -> Self {
+        let perez = Preetham::new(sun_direction, turbidity);
+        let sun = Sun::through_atmosphere(sun_direction, sun_angular_diameter, turbidity);
+
+        // tabulate the upper hemisphere, in the same layout as `LatLong`, measuring the sky's
+        // irradiance on the ground as we go
+        let mut pixels = Vec::with_capacity(TABLE_WIDTH * TABLE_HEIGHT);
+        let mut sky_irradiance = Color::new(0.0, 0.0, 0.0);
+        let d_theta = PI / TABLE_HEIGHT as f64;
+        let d_phi = 2.0 * PI / TABLE_WIDTH as f64;
+        for y in 0..TABLE_HEIGHT / 2 {
+            let theta = (y as f64 + 0.5) * d_theta;
+            for x in 0..TABLE_WIDTH {
+                let phi = (x as f64 + 0.5) * d_phi - PI;
+                let direction = Vec3::new(
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                    -theta.sin() * phi.cos(),
+                );
+                let radiance = perez.radiance(&direction);
+                sky_irradiance += &radiance * (theta.cos() * theta.sin() * d_theta * d_phi);
+                pixels.push(radiance);
+            }
+        }
+
+        let sun_irradiance =
+            sun.disk_radiance() * (sun.solid_angle() * sun.direction().y().max(0.0));
+        let ground = ground_albedo * (sky_irradiance + sun_irradiance) / PI;
+        pixels.resize(TABLE_WIDTH * TABLE_HEIGHT, ground.clone());
+
+        // choose between the sun and the rest of the sky in proportion to their power
+        let sky_power: f64 = pixels
+            .chunks_exact(TABLE_WIDTH)
+            .enumerate()
+            .map(|(y, row)| {
+                let theta = (y as f64 + 0.5) * d_theta;
+                let row_luminance: f64 = row.iter().map(luminance).sum();
+                row_luminance * theta.sin() * d_theta * d_phi
+            })
+            .sum();
+        let sun_power = luminance(sun.disk_radiance()) * sun.solid_angle();
+        let sun_probability = if sun_power > 0.0 {
+            (sun_power / (sun_power + sky_power)).clamp(0.1, 0.9)
+        } else {
+            0.0
+        };
+        // the table's size is fixed, and the pixels were resized to fill it
+        let table = HdrImage::new(TABLE_WIDTH, TABLE_HEIGHT, pixels).expect("sky table is empty");
+
+        Sky {
+            perez,
+            sun,
+            ground,
+            intensity,
+            table: LatLong::new(table, 0.0, 1.0),
+            sun_probability,
+        }
+    }
+}
+
+impl Environment for Sky {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let radiance = if direction.y() < 0.0 {
+            self.ground.clone()
+        } else {
+            self.perez.radiance(direction) + self.sun.radiance(direction)
+        };
+        radiance * self.intensity
+    }
+
+    fn can_sample(&self) -> bool {
+        true
+    }
+
+    fn sample(&self, u: [f64; 2]) -> Option<(Vec3, f64)> {
+        // reuse the first number to pick between the strategies, remapping it to stay uniform
+        let (direction, _) = if u[0] < self.sun_probability {
+            let u0 = u[0] / self.sun_probability;
+            self.sun.sample([u0, u[1]])?
+        } else {
+            let u0 = (u[0] - self.sun_probability) / (1.0 - self.sun_probability);
+            self.table.sample([u0, u[1]])?
+        };
+        let pdf = self.pdf(&direction);
+        Some((direction, pdf))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        self.sun_probability * self.sun.pdf(direction)
+            + (1.0 - self.sun_probability) * self.table.pdf(direction)
+    }
+
+    fn describe(&self, description: &mut Description) {
+        // the table is tabulated from the rest, so needn't be described
+        description
+            .kind("sky")
+            .vec3(&self.perez.sun_direction)
+            .f64s(&self.perez.zenith);
+        for channel in &self.perez.channels {
+            description.f64s(&[channel.a, channel.b, channel.c, channel.d, channel.e]);
+        }
+        self.sun.describe(description);
+        description.vec3(&self.ground).f64(self.intensity);
+    }
+}
+
+/// Coefficients of the Perez sky luminance distribution for one channel.
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    /// The relative luminance at zenith angle `theta` and angle `gamma` from the sun.
+    fn f(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let cos_gamma = gamma.cos();
+        (1.0 + self.a * (self.b / cos_theta).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+/// The analytic sky dome, without the sun, in CIE xyY.
+struct Preetham {
+    sun_direction: Vec3,
+    channels: [Perez; 3],
+    /// `Y`, `x` and `y` at the zenith, each divided by the Perez function there.
+    zenith: [f64; 3],
+}
+
+impl Preetham {
+    fn new(sun_direction: &Vec3, turbidity: f64) -> Self {
+        let t = turbidity;
+        let channels = [
+            Perez {
+                a: 0.1787 * t - 1.4630,
+                b: -0.3554 * t + 0.4275,
+                c: -0.0227 * t + 5.3251,
+                d: 0.1206 * t - 2.5771,
+                e: -0.0670 * t + 0.3703,
+            },
+            Perez {
+                a: -0.0193 * t - 0.2592,
+                b: -0.0665 * t + 0.0008,
+                c: -0.0004 * t + 0.2125,
+                d: -0.0641 * t - 0.8989,
+                e: -0.0033 * t + 0.0452,
+            },
+            Perez {
+                a: -0.0167 * t - 0.2608,
+                b: -0.0950 * t + 0.0092,
+                c: -0.0079 * t + 0.2102,
+                d: -0.0441 * t - 1.6537,
+                e: -0.0109 * t + 0.0529,
+            },
+        ];
+
+        // the model isn't defined for a sun below the horizon, so it's held just above it
+        let sun_direction = sun_direction.unit();
+        let theta_s = sun_direction
+            .y()
+            .clamp(-1.0, 1.0)
+            .acos()
+            .min(PI / 2.0 - 1e-3);
+        let (theta_s2, theta_s3) = (theta_s * theta_s, theta_s * theta_s * theta_s);
+        let t2 = t * t;
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let zenith_x = t2 * (0.00166 * theta_s3 - 0.00375 * theta_s2 + 0.00209 * theta_s)
+            + t * (-0.02903 * theta_s3 + 0.06377 * theta_s2 - 0.03202 * theta_s + 0.00394)
+            + (0.11693 * theta_s3 - 0.21196 * theta_s2 + 0.06052 * theta_s + 0.25886);
+        let zenith_y = t2 * (0.00275 * theta_s3 - 0.00610 * theta_s2 + 0.00317 * theta_s)
+            + t * (-0.04214 * theta_s3 + 0.08970 * theta_s2 - 0.04153 * theta_s + 0.00516)
+            + (0.15346 * theta_s3 - 0.26756 * theta_s2 + 0.06670 * theta_s + 0.26688);
+
+        let zenith = [zenith_luminance, zenith_x, zenith_y];
+        let zenith = [0, 1, 2].map(|i| zenith[i] / channels[i].f(1.0, theta_s));
+        let horizontal = Vec3::new(sun_direction.x(), 0.0, sun_direction.z());
+        let horizontal = if horizontal.is_near_zero() {
+            Vec3::new(1.0, 0.0, 0.0)
+        } else {
+            horizontal.unit()
+        };
+        Preetham {
+            sun_direction: horizontal * theta_s.sin() + Vec3::new(0.0, theta_s.cos(), 0.0),
+            channels,
+            zenith,
+        }
+    }
+
+    /// The sky's radiance in linear sRGB, for directions in the upper hemisphere.
+    fn radiance(&self, direction: &Vec3) -> Color {
+        let direction = direction.unit();
+        // the model diverges at the horizon
+        let cos_theta = direction.y().max(1e-2);
+        let gamma = dot(&direction, &self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] =
+            [0, 1, 2].map(|i| self.zenith[i] * self.channels[i].f(cos_theta, gamma));
+        xyy_to_rgb(x, y, luminance)
+    }
+}
+
+/// Converts CIE xyY to linear sRGB, clamping out of gamut colors.
+fn xyy_to_rgb(x: f64, y: f64, luminance: f64) -> Color {
+    if y <= 0.0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let cie_x = x * luminance / y;
+    let cie_z = (1.0 - x - y) * luminance / y;
+    Color::new(
+        (3.2406 * cie_x - 1.5372 * luminance - 0.4986 * cie_z).max(0.0),
+        (-0.9689 * cie_x + 1.8758 * luminance + 0.0415 * cie_z).max(0.0),
+        (0.0557 * cie_x - 0.2040 * luminance + 1.0570 * cie_z).max(0.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preetham_zenith_luminance() {
+        // normalising by the Perez function and converting to RGB should preserve the zenith
+        // luminance the model is built around
+        let theta_s = PI / 6.0;
+        let sun_direction = Vec3::new(theta_s.sin(), theta_s.cos(), 0.0);
+        let sky = Preetham::new(&sun_direction, 2.0);
+        let res = luminance(&sky.radiance(&Vec3::new(0.0, 1.0, 0.0)));
+
+        let chi = (4.0 / 9.0 - 2.0 / 120.0) * (PI - 2.0 * theta_s);
+        let exp = (4.0453 * 2.0 - 4.9710) * chi.tan() - 0.2155 * 2.0 + 2.4192;
+        assert!((res - exp).abs() < 1e-3 * exp);
+    }
+
+    #[test]
+    fn test_sky_sample_matches_pdf() {
+        let sky = Sky::new(
+            &Vec3::new(1.0, 1.0, -0.5),
+            3.0,
+            Color::new(0.3, 0.3, 0.3),
+            2.0,
+            1.0,
+        );
+
+        for u in [[0.01, 0.5], [0.05, 0.2], [0.3, 0.7], [0.9, 0.1]] {
+            let (direction, pdf) = sky.sample(u).unwrap();
+            assert!(pdf > 0.0);
+            assert!((pdf - sky.pdf(&direction)).abs() < 1e-9 * pdf);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    deg_to_radians,
+    description::Description,
+    onb::Onb,
+    vec3::{dot, Vec3},
+};
+
+use super::environment::Environment;
+
+/// Illuminance of the sun at the top of the atmosphere, in kilolux.
+const SOLAR_ILLUMINANCE: f64 = 128.0;
+
+/// A distant disk of uniform radiance, subtending a cone of directions around `direction`.
+pub struct Sun {
+    direction: Vec3,
+    cos_theta_max: f64,
+    radiance: Color,
+}
+
+impl Sun {
+    /// `angular_diameter` is in degrees; the real sun's is about 0.53.
+    pub fn new(direction: &Vec3, angular_diameter: f64, radiance: Color) -> Self {
+        let half_angle = deg_to_radians(angular_diameter / 2.0).clamp(1e-4, PI / 2.0);
+        Sun {
+            direction: direction.unit(),
+            cos_theta_max: half_angle.cos(),
+            radiance,
+        }
+    }
+
+    /// The sun as seen through a clear atmosphere of the given turbidity, with the radiance in
+    /// kcd/m² scaled to keep its illuminance constant whatever its angular diameter. Only Rayleigh
+    /// and aerosol scattering are accounted for, following Preetham et al. "A Practical Analytic
+    /// Model for Daylight".
+    pub fn through_atmosphere(direction: &Vec3, angular_diameter: f64, turbidity: f64) -> Self {
+        let mut sun = Sun::new(direction, angular_diameter, Color::new(0.0, 0.0, 0.0));
+        let cos_theta = sun.direction.y();
+        if cos_theta <= 0.0 {
+            return sun; // set below the horizon
+        }
+
+        // relative optical mass of the air the light travels through
+        let theta_degrees = cos_theta.acos().to_degrees();
+        let mass = 1.0 / (cos_theta + 0.15 * (93.885 - theta_degrees).powf(-1.253));
+        let beta = 0.04608 * turbidity - 0.04586;
+        let transmittance = |wavelength: f64| {
+            let rayleigh = (-0.008735 * wavelength.powf(-4.08) * mass).exp();
+            let aerosol = (-beta * wavelength.powf(-1.3) * mass).exp();
+            rayleigh * aerosol
+        };
+
+        // representative wavelengths for each channel, in micrometres
+        let radiance = SOLAR_ILLUMINANCE / sun.solid_angle();
+        sun.radiance = Color::new(
+            transmittance(0.65) * radiance,
+            transmittance(0.55) * radiance,
+            transmittance(0.45) * radiance,
+        );
+        sun
+    }
+
+    pub fn direction(&self) -> &Vec3 {
+        &self.direction
+    }
+
+    /// The radiance of every direction within the disk.
+    pub fn disk_radiance(&self) -> &Color {
+        &self.radiance
+    }
+
+    pub fn solid_angle(&self) -> f64 {
+        2.0 * PI * (1.0 - self.cos_theta_max)
+    }
+}
+
+impl Environment for Sun {
+    fn radiance(&self, direction: &Vec3) -> Color {
+        if dot(&direction.unit(), &self.direction) >= self.cos_theta_max {
+            self.radiance.clone()
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    fn can_sample(&self) -> bool {
+        true
+    }
+
+    fn sample(&self, u: [f64; 2]) -> Option<(Vec3, f64)> {
+        // uniform over the cone the disk subtends
+        let z = 1.0 + u[0] * (self.cos_theta_max - 1.0);
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u[1];
+        let onb = Onb::new(&self.direction);
+        let direction = onb.to_world(&Vec3::new(r * phi.cos(), r * phi.sin(), z));
+        Some((direction, 1.0 / self.solid_angle()))
+    }
+
+    fn pdf(&self, direction: &Vec3) -> f64 {
+        if dot(&direction.unit(), &self.direction) >= self.cos_theta_max {
+            1.0 / self.solid_angle()
+        } else {
+            0.0
+        }
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("sun")
+            .vec3(&self.direction)
+            .f64(self.cos_theta_max)
+            .vec3(&self.radiance);
+    }
+}
+
+/// The direction towards the sun from a point on the ground, with -z pointing north and +x east.
+/// `latitude` and `longitude` are in degrees (north and east positive), `standard_meridian` is the
+/// longitude the local time zone is based on, `day_of_year` counts from 1 on January 1st, and
+/// `time` is the local standard time in hours.
+pub fn sun_direction(
+    latitude: f64,
+    longitude: f64,
+    standard_meridian: f64,
+    day_of_year: f64,
+    time: f64,
+) -> Vec3 {
+    let latitude = deg_to_radians(latitude);
+    let day = day_of_year;
+
+    // solar time, corrected for the equation of time and the distance from the time zone's meridian
+    let solar_time = time + 0.170 * (4.0 * PI * (day - 80.0) / 373.0).sin()
+        - 0.129 * (2.0 * PI * (day - 8.0) / 355.0).sin()
+        + (longitude - standard_meridian) / 15.0;
+    let declination = 0.4093 * (2.0 * PI * (day - 81.0) / 368.0).sin();
+    let hour_angle = PI * solar_time / 12.0;
+
+    let elevation = (latitude.sin() * declination.sin()
+        - latitude.cos() * declination.cos() * hour_angle.cos())
+    .asin();
+    // measured from south, positive towards the west
+    let azimuth = (-declination.cos() * hour_angle.sin()).atan2(
+        latitude.cos() * declination.sin() - latitude.sin() * declination.cos() * hour_angle.cos(),
+    );
+
+    Vec3::new(
+        -elevation.cos() * azimuth.sin(),
+        elevation.sin(),
+        elevation.cos() * azimuth.cos(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sun_direction_equinox_noon_at_equator() {
+        let direction = sun_direction(0.0, 0.0, 0.0, 81.0, 12.0);
+
+        assert!(direction.y() > 0.99);
+    }
+
+    #[test]
+    fn test_sun_direction_rises_in_the_east() {
+        let morning = sun_direction(51.5, 0.0, 0.0, 172.0, 8.0);
+        let evening = sun_direction(51.5, 0.0, 0.0, 172.0, 16.0);
+
+        assert!(morning.y() > 0.0 && morning.x() > 0.0);
+        assert!(evening.y() > 0.0 && evening.x() < 0.0);
+    }
+
+    #[test]
+    fn test_sun_sample_within_disk() {
+        let sun = Sun::new(&Vec3::new(0.0, 1.0, 1.0), 5.0, Color::new(1.0, 1.0, 1.0));
+
+        for u in [[0.0, 0.0], [0.5, 0.3], [0.9, 0.9]] {
+            let (direction, pdf) = sun.sample(u).unwrap();
+            assert_eq!(pdf, sun.pdf(&direction));
+            assert_eq!(1.0, sun.radiance(&direction).x());
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{color::Color, interval::Interval, variance_estimator::VarianceEstimator};
+
+/// What one pixel of the film has gathered so far.
+#[derive(Debug, Clone)]
+pub struct FilmPixel {
+    /// The sum of the pixel's filter weighted samples.
+    pub color_sum: Color,
+    pub weight_sum: f64,
+    /// The luminance each sample added to the pixel. Its count is the number of samples taken,
+    /// which is also the index of the next one.
+    pub estimator: VarianceEstimator,
+    /// True once adaptive sampling has judged the pixel to need no more samples.
+    pub converged: bool,
+}
+
+impl FilmPixel {
+    pub fn new() -> Self {
+        FilmPixel {
+            color_sum: Color::new(0.0, 0.0, 0.0),
+            weight_sum: 0.0,
+            estimator: VarianceEstimator::new(),
+            converged: false,
+        }
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.estimator.count()
+    }
+
+    /// The pixel's value: the weighted average of its samples.
+    pub fn color(&self) -> Color {
+        if self.weight_sum == 0.0 {
+            return self.color_sum.clone();
+        }
+        &self.color_sum / self.weight_sum
+    }
+}
+
+impl Default for FilmPixel {
+    fn default() -> Self {
+        FilmPixel::new()
+    }
+}
+
+/// The image a camera accumulates samples into, which can be turned into a picture at any point
+/// during a render.
+#[derive(Debug, Clone, Default)]
+pub struct Film {
+    width: i32,
+    height: i32,
+    /// Row by row, from the top left.
+    pub pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(width: i32, height: i32) -> Self {
+        let pixel_count = (width.max(0) as usize)
+            .checked_mul(height.max(0) as usize)
+            .expect("film is too large");
+        Film {
+            width,
+            height,
+            pixels: vec![FilmPixel::new(); pixel_count],
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The fewest samples any pixel has taken.
+    pub fn min_sample_count(&self) -> usize {
+        self.pixels
+            .iter()
+            .map(FilmPixel::sample_count)
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// The image as a gamma corrected PPM.
+    pub fn to_ppm(&self) -> String {
+        let mut ppm = ppm_header(self.width, self.height);
+        for pixel in &self.pixels {
+            let color = pixel.color();
+            let gamma = Color::new(
+                linear_to_gamma(color.x()),
+                linear_to_gamma(color.y()),
+                linear_to_gamma(color.z()),
+            );
+            ppm.push_str(write_color(&gamma).as_str());
+        }
+        ppm
+    }
+
+    /// A heatmap of the number of samples each pixel has taken, as a PPM image, running from black
+    /// for the fewest through red and yellow to white for the most.
+    pub fn sample_count_heatmap(&self) -> String {
+        let mut ppm = ppm_header(self.width, self.height);
+        let counts = self.pixels.iter().map(FilmPixel::sample_count);
+        let fewest = counts.clone().min().unwrap_or(0);
+        let most = counts.clone().max().unwrap_or(0);
+        for count in counts {
+            let t = if most > fewest {
+                (count - fewest) as f64 / (most - fewest) as f64
+            } else {
+                1.0
+            };
+            let heat = Color::new(3.0 * t, 3.0 * t - 1.0, 3.0 * t - 2.0);
+            ppm.push_str(write_color(&heat).as_str());
+        }
+        ppm
+    }
+}
+
+/// Writes `contents` to a temporary file beside `path`, flushes it to disk and renames it into
+/// place, so that anyone reading `path` sees either the old contents or the new ones, never a
+/// partial write, even if the machine goes down part way. The temporary file's name is unique to
+/// the process and the call, so concurrent writes to the same path don't trample each other.
+pub fn write_atomically(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+    let path = path.as_ref();
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp_path = path.with_file_name(temp_name);
+    let written = File::create_new(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    let renamed = written.and_then(|_| fs::rename(&temp_path, path));
+    if renamed.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    renamed
+}
+
+fn ppm_header(width: i32, height: i32) -> String {
+    format!("P3\n{} {}\n255\n", width, height)
+}
+
+fn write_color(color: &Color) -> String {
+    // translate the color value from the range [0, 1] -> [0, 255]
+    let intensity = Interval::new(0.0, 0.999);
+    let rbyte = (256.0 * intensity.clamp(color.x())) as i32;
+    let gbyte = (256.0 * intensity.clamp(color.y())) as i32;
+    let bbyte = (256.0 * intensity.clamp(color.z())) as i32;
+    format!("{} {} {}\n", rbyte, gbyte, bbyte)
+}
+
+fn linear_to_gamma(linear_component: f64) -> f64 {
+    if linear_component > 0.0 {
+        return linear_component.sqrt();
+    }
+    0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = std::env::temp_dir().join(format!("ray-tracing-test-atomic-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("image.ppm");
+
+        write_atomically(&path, "first").unwrap();
+        write_atomically(&path, "second").unwrap();
+        assert_eq!("second", fs::read_to_string(&path).unwrap());
+        // the temporary files were all renamed away
+        assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+
+        // a failed write leaves no temporary file behind
+        let occupied = dir.join("occupied");
+        fs::create_dir(&occupied).unwrap();
+        assert!(write_atomically(&occupied, "over a directory").is_err());
+        assert_eq!(2, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,33 @@
+use crate::description::Description;
+
+use super::filter::Filter;
+
+/// Weights every sample within the radius equally. With a radius of half a pixel, each pixel is
+/// the plain average of the samples within it.
+pub struct BoxFilter {
+    radius: f64,
+}
+
+impl BoxFilter {
+    pub fn new(radius: f64) -> Self {
+        BoxFilter { radius }
+    }
+}
+
+impl Filter for BoxFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        if x.abs() <= self.radius && y.abs() <= self.radius {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("box").f64(self.radius);
+    }
+}
@@ -0,0 +1,14 @@
+use crate::description::Description;
+
+/// A pixel reconstruction filter, weighting how much a sample counts towards a pixel by its
+/// offset from the pixel's centre.
+pub trait Filter {
+    /// How far from the centre, in pixels, the filter reaches along either axis.
+    fn radius(&self) -> f64;
+
+    /// The filter's value at the offset `(x, y)` from the pixel's centre. Can be negative.
+    fn evaluate(&self, x: f64, y: f64) -> f64;
+
+    /// Adds the kind of filter and its shape to `description`.
+    fn describe(&self, description: &mut Description);
+}
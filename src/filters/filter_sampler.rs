@@ -0,0 +1,97 @@
+use crate::distribution::Distribution2D;
+
+use super::filter::Filter;
+
+/// The number of cells per pixel the filter is tabulated with along each axis.
+const CELLS_PER_PIXEL: f64 = 32.0;
+
+/// Draws sample offsets in proportion to the magnitude of a filter, tabulated on a fine grid, so
+/// that samples can be weighted without sharing them between pixels. Each sample's weight is then
+/// the filter's integral, negated where the filter is negative, which gives the filtered pixel
+/// when the pixel is normalised by the sum of its weights.
+///
+/// This follows pbrt-v4's `FilterSampler`.
+pub struct FilterSampler {
+    radius: f64,
+    /// The number of cells along each axis.
+    resolution: usize,
+    values: Vec<f64>,
+    distribution: Distribution2D,
+    /// The integral of the filter's magnitude.
+    integral: f64,
+}
+
+impl FilterSampler {
+    pub fn new(filter: &dyn Filter) -> Self {
+        let radius = filter.radius().max(1e-3);
+        let resolution = ((2.0 * radius * CELLS_PER_PIXEL).ceil() as usize).max(1);
+        let cell = 2.0 * radius / resolution as f64;
+        let values: Vec<f64> = (0..resolution * resolution)
+            .map(|i| {
+                let x = -radius + (i % resolution) as f64 * cell + cell / 2.0;
+                let y = -radius + (i / resolution) as f64 * cell + cell / 2.0;
+                filter.evaluate(x, y)
+            })
+            .collect();
+        let magnitudes: Vec<f64> = values.iter().map(|v| v.abs()).collect();
+        let integral = magnitudes.iter().sum::<f64>() * cell * cell;
+        FilterSampler {
+            radius,
+            resolution,
+            distribution: Distribution2D::new(&magnitudes, resolution, resolution),
+            values,
+            integral,
+        }
+    }
+
+    /// Maps a pair of uniform random numbers to an offset from the pixel's centre, in pixels,
+    /// returning it and the sample's weight.
+    pub fn sample(&self, u: [f64; 2]) -> ([f64; 2], f64) {
+        let ([x, y], _) = self.distribution.sample(u);
+        let cell_x = ((x * self.resolution as f64) as usize).min(self.resolution - 1);
+        let cell_y = ((y * self.resolution as f64) as usize).min(self.resolution - 1);
+        let value = self.values[cell_y * self.resolution + cell_x];
+        let offset = [
+            -self.radius + 2.0 * self.radius * x,
+            -self.radius + 2.0 * self.radius * y,
+        ];
+        (offset, self.integral.copysign(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::{box_filter::BoxFilter, mitchell_filter::MitchellFilter};
+
+    #[test]
+    fn test_filter_sampler() {
+        let unit = 1.0 / 3.0;
+        let samples = |sampler: &FilterSampler| {
+            (0..64 * 64)
+                .map(|i| {
+                    let u = [
+                        (i % 64) as f64 / 64.0 + 0.0078,
+                        (i / 64) as f64 / 64.0 + 0.0078,
+                    ];
+                    sampler.sample(u)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // a half pixel box keeps samples within the pixel, all weighted the same
+        let box_sampler = FilterSampler::new(&BoxFilter::new(0.5));
+        for ([x, y], weight) in samples(&box_sampler) {
+            assert!(x.abs() <= 0.5 && y.abs() <= 0.5);
+            assert!((weight - 1.0).abs() < 1e-9);
+        }
+
+        // the mitchell filter's negative lobes give some samples negative weights, but in all the
+        // filter integrates to 1
+        let mitchell = FilterSampler::new(&MitchellFilter::new(2.0, unit, unit));
+        let weights: Vec<f64> = samples(&mitchell).into_iter().map(|(_, w)| w).collect();
+        assert!(weights.iter().any(|w| *w < 0.0));
+        let mean = weights.iter().sum::<f64>() / weights.len() as f64;
+        assert!((mean - 1.0).abs() < 0.05, "mean weight {}", mean);
+    }
+}
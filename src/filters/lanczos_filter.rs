@@ -0,0 +1,45 @@
+use std::f64::consts::PI;
+
+use crate::description::Description;
+
+use super::filter::Filter;
+
+/// A sinc, the ideal low-pass filter, windowed by a wider sinc stretched to the radius so that
+/// it reaches zero there. Sharp, but rings around edges.
+pub struct LanczosFilter {
+    radius: f64,
+}
+
+impl LanczosFilter {
+    pub fn new(radius: f64) -> Self {
+        LanczosFilter { radius }
+    }
+
+    fn windowed_sinc(&self, x: f64) -> f64 {
+        if x.abs() > self.radius {
+            return 0.0;
+        }
+        sinc(x) * sinc(x / self.radius)
+    }
+}
+
+impl Filter for LanczosFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.windowed_sinc(x) * self.windowed_sinc(y)
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("lanczos").f64(self.radius);
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        return 1.0;
+    }
+    (PI * x).sin() / (PI * x)
+}
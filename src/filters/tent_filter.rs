@@ -0,0 +1,28 @@
+use crate::description::Description;
+
+use super::filter::Filter;
+
+/// Weights samples falling off linearly to nothing at the radius, along either axis.
+pub struct TentFilter {
+    radius: f64,
+}
+
+impl TentFilter {
+    pub fn new(radius: f64) -> Self {
+        TentFilter { radius }
+    }
+}
+
+impl Filter for TentFilter {
+    fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    fn evaluate(&self, x: f64, y: f64) -> f64 {
+        (self.radius - x.abs()).max(0.0) * (self.radius - y.abs()).max(0.0)
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("tent").f64(self.radius);
+    }
+}
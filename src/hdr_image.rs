@@ -0,0 +1,387 @@
+use std::{fs, io, path::Path};
+
+use crate::{color::Color, description::Description};
+
+/// A floating point image, stored row by row from the top-left corner.
+pub struct HdrImage {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Color>,
+}
+
+impl HdrImage {
+    /// Returns an `InvalidInput` error if the image is empty, as nothing can be looked up or
+    /// sampled in it, or if the number of pixels doesn't match its size.
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> io::Result<Self> {
+        let count = pixel_count(width, height)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error.to_string()))?;
+        if pixels.len() != count {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected {} pixels for a {}x{} image, found {}",
+                    count,
+                    width,
+                    height,
+                    pixels.len()
+                ),
+            ));
+        }
+        Ok(HdrImage {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Loads a Radiance `.hdr` or a `.pfm` image, depending on the file's extension.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("hdr") => HdrImage::from_hdr_bytes(&bytes),
+            Some("pfm") => HdrImage::from_pfm_bytes(&bytes),
+            _ => Err(invalid_data(format!(
+                "unsupported image format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Decodes a Radiance RGBE image, either flat or with new-style run-length encoded scanlines.
+    /// Only the standard `-Y height +X width` orientation is supported.
+    pub fn from_hdr_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = ByteReader { bytes, pos: 0 };
+
+        let magic = reader.line()?;
+        if !magic.starts_with("#?") {
+            return Err(invalid_data("missing Radiance header".to_string()));
+        }
+        loop {
+            let line = reader.line()?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(invalid_data(format!("unsupported format: {}", format)));
+                }
+            }
+        }
+
+        let resolution = reader.line()?;
+        let tokens: Vec<&str> = resolution.split_whitespace().collect();
+        let (height, width) = match tokens[..] {
+            ["-Y", height, "+X", width] => (parse(height)?, parse(width)?),
+            _ => {
+                return Err(invalid_data(format!(
+                    "unsupported resolution: {}",
+                    resolution
+                )))
+            }
+        };
+        // a run encodes at most 127 pixels of a channel in 2 bytes, so no byte holds 16 pixels,
+        // which bounds how large an image the data can hold before anything is allocated for it
+        let data_len = bytes.len() - reader.pos.min(bytes.len());
+        if pixel_count(width, height)? > data_len.saturating_mul(16) {
+            return Err(invalid_data(format!(
+                "{} bytes can't hold a {}x{} image",
+                data_len, width, height
+            )));
+        }
+
+        let mut pixels = Vec::with_capacity(width * height);
+        let mut scanline = vec![0u8; width * 4];
+        for _ in 0..height {
+            reader.scanline(&mut scanline, width)?;
+            pixels.extend(scanline.chunks_exact(4).map(rgbe_to_color));
+        }
+        HdrImage::new(width, height, pixels)
+    }
+
+    /// Decodes a portable float map, in either its color (`PF`) or greyscale (`Pf`) flavour.
+    pub fn from_pfm_bytes(bytes: &[u8]) -> io::Result<Self> {
+        // the header is four whitespace separated tokens, followed by a single whitespace byte
+        let mut reader = ByteReader { bytes, pos: 0 };
+        let mut tokens = Vec::with_capacity(4);
+        while tokens.len() < 4 {
+            tokens.push(reader.token()?);
+        }
+        reader.pos += 1;
+
+        let channels = match tokens[0].as_str() {
+            "PF" => 3,
+            "Pf" => 1,
+            _ => return Err(invalid_data("missing PFM header".to_string())),
+        };
+        let width: usize = parse(&tokens[1])?;
+        let height: usize = parse(&tokens[2])?;
+        let scale: f64 = parse(&tokens[3])?;
+        // the sign of the scale gives the byte order, and its magnitude scales the values
+        let little_endian = scale < 0.0;
+
+        let data = &bytes[reader.pos.min(bytes.len())..];
+        let expected_len = pixel_count(width, height)?
+            .checked_mul(channels * 4)
+            .ok_or_else(|| invalid_data("image is too large".to_string()))?;
+        if data.len() < expected_len {
+            return Err(invalid_data(format!(
+                "expected {} bytes of pixel data, found {}",
+                expected_len,
+                data.len()
+            )));
+        }
+
+        let values: Vec<f64> = data[..expected_len]
+            .chunks_exact(4)
+            .map(|b| {
+                let b = [b[0], b[1], b[2], b[3]];
+                let value = if little_endian {
+                    f32::from_le_bytes(b)
+                } else {
+                    f32::from_be_bytes(b)
+                };
+                value as f64 * scale.abs()
+            })
+            .collect();
+
+        // rows are stored from the bottom up
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in values.chunks_exact(width * channels).rev() {
+            pixels.extend(row.chunks_exact(channels).map(|c| match c {
+                [v] => Color::new(*v, *v, *v),
+                _ => Color::new(c[0], c[1], c[2]),
+            }));
+        }
+        HdrImage::new(width, height, pixels)
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> &Color {
+        &self.pixels[y * self.width + x]
+    }
+
+    /// Adds the image's size and pixels to `description`.
+    pub fn describe(&self, description: &mut Description) {
+        description.u64(self.width as u64).u64(self.height as u64);
+        for pixel in &self.pixels {
+            description.vec3(pixel);
+        }
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl ByteReader<'_> {
+    fn byte(&mut self) -> io::Result<u8> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    /// Reads up to the next newline, which is consumed but not returned.
+    fn line(&mut self) -> io::Result<String> {
+        let mut line = Vec::new();
+        loop {
+            match self.byte()? {
+                b'\n' => break,
+                byte => line.push(byte),
+            }
+        }
+        Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+    }
+
+    /// Skips leading whitespace and reads up to the next whitespace byte, which isn't consumed.
+    fn token(&mut self) -> io::Result<String> {
+        while self.byte()?.is_ascii_whitespace() {}
+        let start = self.pos - 1;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.pos]).to_string())
+    }
+
+    /// Reads one scanline of RGBE pixels into `scanline`.
+    fn scanline(&mut self, scanline: &mut [u8], width: usize) -> io::Result<()> {
+        let rest = &self.bytes[self.pos..];
+        let is_rle = (8..0x8000).contains(&width)
+            && rest.len() >= 4
+            && rest[0] == 2
+            && rest[1] == 2
+            && rest[2] & 0x80 == 0;
+        if !is_rle {
+            for byte in scanline.iter_mut() {
+                *byte = self.byte()?;
+            }
+            return Ok(());
+        }
+
+        let encoded_width = ((rest[2] as usize) << 8) | rest[3] as usize;
+        if encoded_width != width {
+            return Err(invalid_data("scanline width mismatch".to_string()));
+        }
+        self.pos += 4;
+
+        // each channel is run-length encoded separately
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = self.byte()? as usize;
+                let (count, is_run) = if count > 128 {
+                    (count - 128, true)
+                } else {
+                    (count, false)
+                };
+                if count == 0 || x + count > width {
+                    return Err(invalid_data("bad scanline run".to_string()));
+                }
+                let value = if is_run { self.byte()? } else { 0 };
+                for _ in 0..count {
+                    scanline[x * 4 + channel] = if is_run { value } else { self.byte()? };
+                    x += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn rgbe_to_color(rgbe: &[u8]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::new(0.0, 0.0, 0.0);
+    }
+    let scale = 2f64.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(
+        (rgbe[0] as f64 + 0.5) * scale,
+        (rgbe[1] as f64 + 0.5) * scale,
+        (rgbe[2] as f64 + 0.5) * scale,
+    )
+}
+
+fn parse<T: std::str::FromStr>(token: &str) -> io::Result<T> {
+    token
+        .parse()
+        .map_err(|_| invalid_data(format!("expected a number, found {}", token)))
+}
+
+/// The number of pixels in an image of the given size, which must be non-empty.
+fn pixel_count(width: usize, height: usize) -> io::Result<usize> {
+    if width == 0 || height == 0 {
+        return Err(invalid_data(format!(
+            "image must not be empty, found {}x{}",
+            width, height
+        )));
+    }
+    width
+        .checked_mul(height)
+        .ok_or_else(|| invalid_data("image is too large".to_string()))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_rejects_empty_and_mismatched_pixels() {
+        let grey = Color::new(0.5, 0.5, 0.5);
+        for (width, height, count) in [(0, 2, 0), (2, 0, 0), (2, 2, 3), (2, 2, 5)] {
+            let error = HdrImage::new(width, height, vec![grey.clone(); count]).err();
+            assert_eq!(io::ErrorKind::InvalidInput, error.unwrap().kind());
+        }
+        assert!(HdrImage::new(2, 2, vec![grey; 4]).is_ok());
+    }
+
+    #[test]
+    fn test_from_pfm_bytes() {
+        let mut bytes = b"PF\n2 2\n-1.0\n".to_vec();
+        for v in [
+            1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0, 0.0, 0.0, 0.0, 0.5, 0.5, 0.5,
+        ] {
+            bytes.extend(v.to_le_bytes());
+        }
+
+        let image = HdrImage::from_pfm_bytes(&bytes).unwrap();
+        assert_eq!((2, 2), (image.width, image.height));
+        // the first row in the file is the bottom one
+        assert_eq!(0.0, image.pixel(0, 0).x());
+        assert_eq!(0.5, image.pixel(1, 0).z());
+        assert_eq!(1.0, image.pixel(0, 1).x());
+        assert_eq!(6.0, image.pixel(1, 1).z());
+    }
+
+    #[test]
+    fn test_from_pfm_bytes_rejects_bad_dimensions() {
+        for header in [
+            "PF\n0 2\n-1.0\n",
+            "Pf\n2 0\n-1.0\n",
+            "PF\n18446744073709551615 2\n-1.0\n",
+            "PF\n4294967296 4294967296\n-1.0\n",
+        ] {
+            let mut bytes = header.as_bytes().to_vec();
+            bytes.extend([0; 24]);
+            let res = HdrImage::from_pfm_bytes(&bytes);
+
+            assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        }
+    }
+
+    #[test]
+    fn test_from_hdr_bytes_rejects_bad_dimensions() {
+        for resolution in ["-Y 0 +X 2", "-Y 1 +X 0", "-Y 1000000 +X 1000000"] {
+            let mut bytes = format!("#?RADIANCE\n\n{}\n", resolution).into_bytes();
+            bytes.extend([128, 64, 0, 129, 0, 0, 0, 0]);
+            let res = HdrImage::from_hdr_bytes(&bytes);
+
+            assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        }
+    }
+
+    #[test]
+    fn test_from_hdr_bytes_flat() {
+        let mut bytes = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        bytes.extend([128, 64, 0, 129, 0, 0, 0, 0]);
+
+        let image = HdrImage::from_hdr_bytes(&bytes).unwrap();
+        let c = image.pixel(0, 0);
+        assert!((c.x() - 128.5 / 128.0).abs() < 1e-12);
+        assert!((c.y() - 64.5 / 128.0).abs() < 1e-12);
+        assert!((c.z() - 0.5 / 128.0).abs() < 1e-12);
+        assert!(image.pixel(1, 0).is_near_zero());
+    }
+
+    #[test]
+    fn test_from_hdr_bytes_run_length_encoded() {
+        let mut bytes = b"#?RGBE\n\n-Y 1 +X 8\n".to_vec();
+        bytes.extend([2, 2, 0, 8]);
+        // red: a run of 8, green: 8 literals, blue: two runs of 4, exponent: a run of 8
+        bytes.extend([136, 10]);
+        bytes.extend([8, 0, 1, 2, 3, 4, 5, 6, 7]);
+        bytes.extend([132, 0, 132, 255]);
+        bytes.extend([136, 136]);
+
+        let image = HdrImage::from_hdr_bytes(&bytes).unwrap();
+        // an exponent of 136 leaves the mantissas unscaled
+        assert_eq!((8, 1), (image.width, image.height));
+        assert_eq!(10.5, image.pixel(3, 0).x());
+        assert_eq!(5.5, image.pixel(5, 0).y());
+        assert_eq!(0.5, image.pixel(3, 0).z());
+        assert_eq!(255.5, image.pixel(4, 0).z());
+    }
+}
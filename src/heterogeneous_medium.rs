@@ -0,0 +1,99 @@
+use crate::{
+    aabb::Aabb, constant_medium::inside_boundary, density::DensityField, description::Description,
+    hit_record::HitRecord, hittable::Hittable, interval::Interval, materials::material::Material,
+    ray::Ray, vec3::Vec3,
+};
+
+/// A participating medium whose density varies through the volume enclosed by `boundary`.
+///
+/// Scattering distances are sampled with delta tracking and shadow rays are attenuated with ratio
+/// tracking, both against the density field's maximum. These are unbiased however the density
+/// varies, but get slower as the maximum grows relative to the typical density.
+///
+/// As with `ConstantMedium`, the boundary must be closed and convex.
+pub struct HeterogeneousMedium<'a> {
+    boundary: &'a dyn Hittable,
+    density: &'a dyn DensityField,
+    phase_function: &'a dyn Material,
+}
+
+impl<'a> HeterogeneousMedium<'a> {
+    pub fn new(
+        boundary: &'a dyn Hittable,
+        density: &'a dyn DensityField,
+        phase_function: &'a dyn Material,
+    ) -> Self {
+        HeterogeneousMedium {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+}
+
+impl<'a> Hittable for HeterogeneousMedium<'a> {
+    /// Delta tracking: take exponentially distributed steps through a fictitious medium of the
+    /// maximum density, and at each step accept a real collision with probability
+    /// `density / max_density`.
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord<'_>> {
+        let inside = inside_boundary(self.boundary, ray, &ray_t)?;
+        let max_density = self.density.max_density();
+        if max_density <= 0.0 {
+            return None;
+        }
+
+        let ray_length = ray.direction.len();
+        let mut rng = ray.rng(inside.min, 0);
+        let mut t = inside.min;
+        loop {
+            t -= (1.0 - rng.next_f64()).ln() / (max_density * ray_length);
+            if t >= inside.max {
+                return None;
+            }
+            let point = ray.at(t);
+            if rng.next_f64() * max_density < self.density.density(&point) {
+                // a medium has no surface, so the normal and face are arbitrary
+                let normal = Vec3::new(1.0, 0.0, 0.0);
+                return Some(HitRecord::new(point, normal, t, true, self.phase_function));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    /// Ratio tracking: take the same steps as delta tracking, but instead of stopping at a real
+    /// collision, weight the estimate by the probability of it being a null one.
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        let Some(inside) = inside_boundary(self.boundary, ray, &ray_t) else {
+            return 1.0;
+        };
+        let max_density = self.density.max_density();
+        if max_density <= 0.0 {
+            return 1.0;
+        }
+
+        let ray_length = ray.direction.len();
+        let mut rng = ray.rng(inside.min, 1);
+        let mut transmittance = 1.0;
+        let mut t = inside.min;
+        loop {
+            t -= (1.0 - rng.next_f64()).ln() / (max_density * ray_length);
+            if t >= inside.max {
+                return transmittance;
+            }
+            transmittance *= 1.0 - self.density.density(&ray.at(t)) / max_density;
+            if transmittance <= 0.0 {
+                return 0.0;
+            }
+        }
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("heterogeneous medium");
+        self.density.describe(description);
+        self.phase_function.describe(description);
+        self.boundary.describe(description);
+    }
+}
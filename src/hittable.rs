@@ -1,5 +1,45 @@
-use crate::{hit_record::HitRecord, interval::Interval, ray::Ray};
+use crate::{
+    aabb::Aabb, description::Description, hit_record::HitRecord, interval::Interval, point::Point,
+    ray::Ray, vec3::Vec3,
+};
 
 pub trait Hittable {
-    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord>;
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord<'_>>;
+
+    /// A box enclosing everything the hittable can be hit at.
+    fn bounding_box(&self) -> Aabb;
+
+    /// The fraction of light that makes it through the hittable along the ray, within `ray_t`.
+    /// Solid hittables either block the ray or don't; participating media attenuate it.
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        if self.hit(ray, ray_t).is_some() {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// The density, per unit solid angle as seen from `origin`, with which `random` returns
+    /// `direction`. Only hittables that can be sampled as lights need to implement this.
+    fn pdf_value(&self, _origin: &Point, _direction: &Vec3) -> f64 {
+        0.0
+    }
+
+    /// Returns a direction from `origin` towards a random point on the hittable, so it can be
+    /// sampled as a light. `u` holds two uniform random numbers in `[0, 1)` that choose the point.
+    fn random(&self, _origin: &Point, _u: [f64; 2]) -> Vec3 {
+        Vec3::new(1.0, 0.0, 0.0)
+    }
+
+    /// A rough estimate of the total power the hittable emits, for deciding how often to sample
+    /// it as a light. Power based selection never picks lights reporting no power, unless none of
+    /// the lights report any, when it picks between them uniformly. The light BVH always leaves
+    /// them out.
+    fn power(&self) -> f64 {
+        0.0
+    }
+
+    /// Adds the kind of hittable and everything it's built from, its material included, to
+    /// `description`.
+    fn describe(&self, description: &mut Description);
 }
@@ -0,0 +1,265 @@
+use crate::{
+    aabb::Aabb, description::Description, hit_record::HitRecord, hittable::Hittable,
+    interval::Interval, point::Point, ray::Ray, transform::Transform, vec3::Vec3,
+};
+
+/// A hittable placed in the scene by a transform, which can change over time to blur the object
+/// as it moves, turns and grows. The same object can be shared by any number of instances.
+pub struct Instance<'a> {
+    object: &'a dyn Hittable,
+    /// The transform at `time0`, or at all times if the instance doesn't move.
+    start: Transform,
+    /// The transform at `time1`.
+    end: Transform,
+    time0: f64,
+    time1: f64,
+    bbox: Aabb,
+}
+
+impl<'a> Instance<'a> {
+    pub fn new(object: &'a dyn Hittable, transform: Transform) -> Self {
+        Instance::new_moving(object, transform.clone(), transform, 0.0, 0.0)
+    }
+
+    /// An instance whose transform is interpolated from `start` at `time0` to `end` at `time1`,
+    /// and holds at either end outside that interval. The rotation takes the shorter way round,
+    /// so it can turn through less than half a turn between the two; spinning further needs
+    /// nesting instances or splitting the shutter interval.
+    ///
+    /// As with moving spheres, moving instances can't be sampled as lights.
+    pub fn new_moving(
+        object: &'a dyn Hittable,
+        start: Transform,
+        end: Transform,
+        time0: f64,
+        time1: f64,
+    ) -> Self {
+        let bbox = swept_bounds(&object.bounding_box(), &start, &end);
+        Instance {
+            object,
+            start,
+            end,
+            time0,
+            time1,
+            bbox,
+        }
+    }
+
+    fn is_moving(&self) -> bool {
+        !(&self.end.translation - &self.start.translation).is_near_zero()
+            || !(&self.end.scale - &self.start.scale).is_near_zero()
+            || self.start.rotation.angle_to(&self.end.rotation) > 1e-8
+    }
+
+    /// The transform at `time`.
+    fn transform_at(&self, time: f64) -> Transform {
+        if self.time1 <= self.time0 {
+            return if time < self.time0 {
+                self.start.clone()
+            } else {
+                self.end.clone()
+            };
+        }
+        let t = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        Transform::interpolate(&self.start, &self.end, t)
+    }
+
+    /// The transform to sample the object as a light through, if it can be. Solid angles only
+    /// carry over unchanged when the instance is still and scaled the same along every axis.
+    fn light_transform(&self) -> Option<&Transform> {
+        let scale = &self.start.scale;
+        let uniform = (scale.x().abs() - scale.y().abs()).abs() < 1e-8
+            && (scale.x().abs() - scale.z().abs()).abs() < 1e-8;
+        (uniform && !self.is_moving()).then_some(&self.start)
+    }
+}
+
+impl<'a> Hittable for Instance<'a> {
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord<'_>> {
+        // the direction isn't renormalised, so distances along the ray are the same either side
+        let transform = self.transform_at(ray.time);
+        let local_ray = ray.spawn(
+            transform.point_to_local(&ray.origin),
+            transform.vector_to_local(&ray.direction),
+        );
+        let mut hit = self.object.hit(&local_ray, ray_t)?;
+        hit.point = transform.point_to_world(&hit.point);
+        hit.normal = transform.normal_to_world(&hit.normal).unit();
+        Some(hit)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn transmittance(&self, ray: &Ray, ray_t: Interval) -> f64 {
+        let transform = self.transform_at(ray.time);
+        let local_ray = ray.spawn(
+            transform.point_to_local(&ray.origin),
+            transform.vector_to_local(&ray.direction),
+        );
+        self.object.transmittance(&local_ray, ray_t)
+    }
+
+    fn pdf_value(&self, origin: &Point, direction: &Vec3) -> f64 {
+        let Some(transform) = self.light_transform() else {
+            return 0.0;
+        };
+        self.object.pdf_value(
+            &transform.point_to_local(origin),
+            &transform.vector_to_local(direction),
+        )
+    }
+
+    fn random(&self, origin: &Point, u: [f64; 2]) -> Vec3 {
+        let Some(transform) = self.light_transform() else {
+            return Vec3::new(1.0, 0.0, 0.0);
+        };
+        transform.vector_to_world(&self.object.random(&transform.point_to_local(origin), u))
+    }
+
+    fn power(&self) -> f64 {
+        // instances that can't be sampled as lights shouldn't be picked as them
+        let Some(transform) = self.light_transform() else {
+            return 0.0;
+        };
+        // the scale is uniform, so surface area grows with its square
+        self.object.power() * transform.scale.x() * transform.scale.x()
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("instance");
+        self.start.describe(description);
+        self.end.describe(description);
+        description.f64(self.time0).f64(self.time1);
+        self.object.describe(description);
+    }
+}
+
+/// A box enclosing the object's box `bbox` everywhere the transform carries it between `start`
+/// and `end`.
+///
+/// The corners' paths are followed in steps of at most an eighth of a turn. Between steps, a
+/// corner at `p(s) = T(s) + R(s)v(s)` (where `v` is its scaled position) strays from the
+/// straight line between the ends by at most `max|p''| / 8`. The translation and `v` are linear,
+/// so with the step turning through `θ` that's at most `(θ²|v| + 2θ|v1 - v0|) / 8`, and padding
+/// each step's box by it covers the arc.
+fn swept_bounds(bbox: &Aabb, start: &Transform, end: &Transform) -> Aabb {
+    let corners: Vec<Point> = (0..8)
+        .map(|i| {
+            Point::new(
+                if i & 1 == 0 { bbox.x.min } else { bbox.x.max },
+                if i & 2 == 0 { bbox.y.min } else { bbox.y.max },
+                if i & 4 == 0 { bbox.z.min } else { bbox.z.max },
+            )
+        })
+        .collect();
+
+    let angle = start.rotation.angle_to(&end.rotation);
+    let steps = ((angle / (std::f64::consts::PI / 4.0)).ceil() as usize).max(1);
+    let step_angle = angle / steps as f64;
+
+    let mut swept = Aabb::empty();
+    let mut prev = start.clone();
+    for step in 1..=steps {
+        let next = Transform::interpolate(start, end, step as f64 / steps as f64);
+        for corner in &corners {
+            let v0 = corner * &prev.scale;
+            let v1 = corner * &next.scale;
+            let max_len = v0.len().max(v1.len());
+            let deviation =
+                (step_angle * step_angle * max_len + 2.0 * step_angle * (&v1 - &v0).len()) / 8.0;
+            let segment =
+                Aabb::from_points(&prev.point_to_world(corner), &next.point_to_world(corner));
+            swept = Aabb::enclosing(&swept, &segment.expand(2.0 * deviation));
+        }
+        prev = next;
+    }
+    swept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        materials::{diffuse_light::DiffuseLight, lambertian::Lambertian},
+        quaternion::Quaternion,
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn test_instance_hit() {
+        let material = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let sphere = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0, &material);
+        // stretched to twice as tall, then moved up and back
+        let transform = Transform::new(
+            Vec3::new(0.0, 1.0, -5.0),
+            Quaternion::identity(),
+            Vec3::new(1.0, 2.0, 1.0),
+        );
+        let instance = Instance::new(&sphere, transform);
+
+        let ray = Ray::new(Point::new(0.0, 2.5, 0.0), Vec3::new(0.0, 0.0, -2.0), 0.0);
+        let hit = instance
+            .hit(&ray, Interval::new(0.001, f64::INFINITY))
+            .unwrap();
+        assert!((hit.point.y() - 2.5).abs() < 1e-9);
+        assert!(hit.point.z() > -5.0 && hit.point.z() < -4.0);
+        assert!((&ray.at(hit.t) - &hit.point).len() < 1e-9);
+        assert!((hit.normal.len() - 1.0).abs() < 1e-9);
+        assert!(hit.normal.y() > 0.0 && hit.normal.z() > 0.0);
+    }
+
+    #[test]
+    fn test_power_only_for_lights() {
+        let light = DiffuseLight::new(Color::new(1.0, 1.0, 1.0));
+        let sphere = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0, &light);
+        let scaled =
+            |scale: Vec3| Transform::new(Vec3::new(0.0, 0.0, -5.0), Quaternion::identity(), scale);
+        let uniform = Instance::new(&sphere, scaled(Vec3::new(2.0, 2.0, -2.0)));
+        let stretched = Instance::new(&sphere, scaled(Vec3::new(1.0, 2.0, 1.0)));
+        let moving = Instance::new_moving(
+            &sphere,
+            scaled(Vec3::new(1.0, 1.0, 1.0)),
+            Transform::identity(),
+            0.0,
+            1.0,
+        );
+
+        assert!((uniform.power() - 4.0 * sphere.power()).abs() < 1e-9 * sphere.power());
+        assert_eq!(0.0, stretched.power());
+        assert_eq!(0.0, moving.power());
+    }
+
+    #[test]
+    fn test_swept_bounds() {
+        let material = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let sphere = Sphere::new(Point::new(1.0, 0.0, 0.0), 0.1, &material);
+        let axis = Vec3::new(0.0, 1.0, 0.0);
+        let start = Transform::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Quaternion::identity(),
+            Vec3::new(1.0, 1.0, 1.0),
+        );
+        let end = Transform::new(
+            Vec3::new(0.0, 0.5, 0.0),
+            Quaternion::from_axis_angle(&axis, 170.0),
+            Vec3::new(2.0, 2.0, 2.0),
+        );
+        let instance = Instance::new_moving(&sphere, start, end, 0.0, 1.0);
+
+        // the boxes at either end miss most of the arc the sphere swings through
+        let bbox = instance.bounding_box();
+        for i in 0..=100 {
+            let transform = instance.transform_at(i as f64 / 100.0);
+            let centre = transform.point_to_world(&Point::new(1.0, 0.0, 0.0));
+            let radius = 0.1 * transform.scale.x();
+            for axis in 0..3 {
+                let interval = bbox.axis(axis);
+                assert!(interval.min <= centre[axis] - radius);
+                assert!(centre[axis] + radius <= interval.max);
+            }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    description::Description,
+    light_samplers::{
+        bvh::Bvh,
+        light_sampler::{LightBounds, LightSampler},
+        power::Power,
+        uniform::Uniform,
+    },
+    scene::Scene,
+};
+
+/// Settings for how `Ray::color` estimates the light arriving along a ray.
+pub struct Integrator {
+    /// The maximum number of bounces a path can make before it stops gathering light. Russian
+    /// roulette usually ends paths well before this; it is a safety net that introduces bias
+    /// when reached.
+    pub max_depth: i32,
+    /// The number of bounces after which paths may be terminated by Russian roulette.
+    pub rr_min_depth: i32,
+    /// Whether to combine light sampling and BSDF sampling with multiple importance sampling.
+    /// When disabled, emitters in the scene's light list are only reached by light sampling,
+    /// which is noisier on glossy surfaces and large lights. Listed emitters that can't be
+    /// sampled, like moving spheres, are still reached by BSDF sampling.
+    pub mis: bool,
+    /// How to choose which light to sample for direct lighting.
+    pub light_selection: LightSelection,
+}
+
+/// Strategies for choosing a light to sample, from cheapest to most effective in scenes with many
+/// lights.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightSelection {
+    /// Every light is equally likely.
+    Uniform,
+    /// Lights are picked in proportion to their power.
+    Power,
+    /// Lights are picked by their power and distance from the point being shaded.
+    Bvh,
+}
+
+impl Integrator {
+    /// Builds the light sampler for `scene` that `light_selection` asks for.
+    pub fn light_sampler(&self, scene: &Scene) -> Box<dyn LightSampler> {
+        match self.light_selection {
+            LightSelection::Uniform => Box::new(Uniform::new(scene.light_count())),
+            LightSelection::Power => Box::new(Power::new(&LightBounds::for_scene(scene))),
+            LightSelection::Bvh => Box::new(Bvh::new(&LightBounds::for_scene(scene))),
+        }
+    }
+
+    /// Adds the integrator's settings to `description`.
+    pub fn describe(&self, description: &mut Description) {
+        description
+            .u64(self.max_depth as u64)
+            .u64(self.rr_min_depth as u64)
+            .u64(self.mis as u64)
+            .u64(self.light_selection as u64);
+    }
+}
+
+impl Default for Integrator {
+    fn default() -> Self {
+        Integrator {
+            max_depth: 1000,
+            rr_min_depth: 3,
+            mis: true,
+            light_selection: LightSelection::Bvh,
+        }
+    }
+}
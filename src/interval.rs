@@ -1,3 +1,4 @@
+#[derive(Debug, Clone)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
@@ -10,15 +11,32 @@ impl Interval {
 
     pub fn empty() -> Self {
         Interval {
-            min: std::f64::INFINITY,
-            max: -std::f64::INFINITY,
+            min: f64::INFINITY,
+            max: -f64::INFINITY,
         }
     }
 
     pub fn universe() -> Self {
         Interval {
-            min: -std::f64::INFINITY,
-            max: std::f64::INFINITY,
+            min: -f64::INFINITY,
+            max: f64::INFINITY,
+        }
+    }
+
+    /// The smallest interval containing both `a` and `b`.
+    pub fn enclosing(a: &Interval, b: &Interval) -> Self {
+        Interval {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
+    /// Grows the interval by `delta` in total, split evenly between either end.
+    pub fn expand(&self, delta: f64) -> Self {
+        let padding = delta / 2.0;
+        Interval {
+            min: self.min - padding,
+            max: self.max + padding,
         }
     }
 
@@ -0,0 +1,45 @@
+pub mod aabb;
+pub mod adaptive_sampling;
+pub mod apertures;
+pub mod camera;
+pub mod checkpoint;
+pub mod color;
+pub mod constant_medium;
+pub mod cuboid;
+pub mod density;
+pub mod description;
+pub mod distribution;
+pub mod environments;
+pub mod film;
+pub mod filters;
+pub mod hdr_image;
+pub mod heterogeneous_medium;
+pub mod hit_record;
+pub mod hittable;
+pub mod instance;
+pub mod integrator;
+pub mod interval;
+pub mod light_samplers;
+pub mod lights;
+pub mod materials;
+pub mod onb;
+pub mod pcg;
+pub mod pdf;
+pub mod perlin;
+pub mod point;
+pub mod progressive;
+pub mod projections;
+pub mod quad;
+pub mod quaternion;
+pub mod ray;
+pub mod samplers;
+pub mod scene;
+pub mod sphere;
+pub mod stereo;
+pub mod transform;
+pub mod variance_estimator;
+pub mod vec3;
+
+pub fn deg_to_radians(degrees: f64) -> f64 {
+    degrees * std::f64::consts::PI / 180.0
+}
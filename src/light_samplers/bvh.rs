@@ -0,0 +1,240 @@
+use crate::{aabb::Aabb, point::Point};
+
+use super::light_sampler::{InfiniteLights, LightBounds, LightSampler, Split};
+
+/// Picks finite lights by their estimated contribution to the point being shaded, by descending a
+/// bounding volume hierarchy over them and choosing between the two children at each level in
+/// proportion to their power over their squared distance. This makes nearby lights much more
+/// likely to be picked than distant ones, which matters in scenes with many small emitters.
+///
+/// Orientation isn't taken into account, so one-sided emitters facing away from the point are
+/// still picked as if they faced it.
+pub struct Bvh {
+    infinite: InfiniteLights,
+    nodes: Vec<Node>,
+    /// For each light in the tree, the path from the root to its leaf: bit `d` is set if the
+    /// path takes the second child at depth `d`.
+    trails: Vec<Option<u64>>,
+}
+
+struct Node {
+    bounds: Aabb,
+    power: f64,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    /// Holds the index of a single light.
+    Leaf(usize),
+    /// The first child immediately follows the node; this is the index of the second.
+    Interior(usize),
+}
+
+/// A light being placed in the tree.
+struct Item {
+    index: usize,
+    bounds: Aabb,
+    centroid: Point,
+    power: f64,
+}
+
+impl Bvh {
+    pub fn new(lights: &[LightBounds]) -> Self {
+        // lights that give off no power would never be picked, so they're left out of the tree
+        let mut items: Vec<Item> = lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| light.power > 0.0)
+            .filter_map(|(index, light)| {
+                let bounds = light.bounds.clone()?;
+                Some(Item {
+                    index,
+                    centroid: bounds.centroid(),
+                    bounds,
+                    power: light.power,
+                })
+            })
+            .collect();
+
+        let mut nodes = Vec::with_capacity(2 * items.len());
+        let mut trails = vec![None; lights.len()];
+        if !items.is_empty() {
+            build(&mut items, &mut nodes, &mut trails, 0, 0);
+        }
+
+        Bvh {
+            infinite: InfiniteLights::new(lights, !nodes.is_empty()),
+            nodes,
+            trails,
+        }
+    }
+}
+
+/// Builds the subtree over `items`, splitting them in half along the axis their centroids are
+/// most spread out on.
+fn build(
+    items: &mut [Item],
+    nodes: &mut Vec<Node>,
+    trails: &mut [Option<u64>],
+    trail: u64,
+    depth: u32,
+) {
+    let bounds = items.iter().fold(Aabb::empty(), |bounds, item| {
+        Aabb::enclosing(&bounds, &item.bounds)
+    });
+    let power = items.iter().map(|item| item.power).sum();
+
+    if let [item] = items {
+        trails[item.index] = Some(trail);
+        nodes.push(Node {
+            bounds,
+            power,
+            kind: NodeKind::Leaf(item.index),
+        });
+        return;
+    }
+
+    let centroid_bounds = items.iter().fold(Aabb::empty(), |bounds, item| {
+        Aabb::enclosing(&bounds, &Aabb::from_points(&item.centroid, &item.centroid))
+    });
+    let axis = centroid_bounds.longest_axis();
+    items.sort_by(|a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+    let (first, second) = items.split_at_mut(items.len() / 2);
+
+    let node = nodes.len();
+    nodes.push(Node {
+        bounds,
+        power,
+        kind: NodeKind::Interior(0),
+    });
+    build(first, nodes, trails, trail, depth + 1);
+    nodes[node].kind = NodeKind::Interior(nodes.len());
+    build(second, nodes, trails, trail | (1 << depth), depth + 1);
+}
+
+/// An estimate of how much light the node's lights cast on `point`.
+fn importance(node: &Node, point: &Point) -> f64 {
+    let distance_sq = (&node.bounds.centroid() - point).len_sq();
+    // within the bounds, the distance to the centroid says little about the distance to the
+    // lights, so the falloff is capped at the bounds' size
+    let radius_sq = node.bounds.diagonal().len_sq() / 4.0;
+    node.power / distance_sq.max(radius_sq).max(1e-6)
+}
+
+/// The probability of descending to the first of the two children of an interior node.
+fn first_child_probability(nodes: &[Node], node: usize, second: usize, point: &Point) -> f64 {
+    let first_importance = importance(&nodes[node + 1], point);
+    let second_importance = importance(&nodes[second], point);
+    let total = first_importance + second_importance;
+    if total > 0.0 {
+        first_importance / total
+    } else {
+        0.5
+    }
+}
+
+impl LightSampler for Bvh {
+    fn sample(&self, point: &Point, u: f64) -> Option<(usize, f64)> {
+        let mut u = match self.infinite.split(u) {
+            Split::Infinite(index) => return Some((index, self.infinite.pmf())),
+            Split::Finite(u) => u,
+        };
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut node = 0;
+        let mut pmf = self.infinite.finite_probability();
+        loop {
+            match self.nodes[node].kind {
+                NodeKind::Leaf(index) => return Some((index, pmf)),
+                NodeKind::Interior(second) => {
+                    let p = first_child_probability(&self.nodes, node, second, point);
+                    // reuse the random number, remapping it to stay uniform
+                    if u < p {
+                        u /= p;
+                        pmf *= p;
+                        node += 1;
+                    } else {
+                        u = (u - p) / (1.0 - p);
+                        pmf *= 1.0 - p;
+                        node = second;
+                    }
+                    u = u.min(1.0 - f64::EPSILON);
+                }
+            }
+        }
+    }
+
+    fn pmf(&self, point: &Point, index: usize) -> f64 {
+        if self.infinite.contains(index) {
+            return self.infinite.pmf();
+        }
+        let Some(Some(trail)) = self.trails.get(index) else {
+            return 0.0;
+        };
+
+        let mut node = 0;
+        let mut depth = 0;
+        let mut pmf = self.infinite.finite_probability();
+        while let NodeKind::Interior(second) = self.nodes[node].kind {
+            let p = first_child_probability(&self.nodes, node, second, point);
+            if trail & (1 << depth) == 0 {
+                pmf *= p;
+                node += 1;
+            } else {
+                pmf *= 1.0 - p;
+                node = second;
+            }
+            depth += 1;
+        }
+        pmf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn light_at(x: f64, power: f64) -> LightBounds {
+        let p = Point::new(x, 0.0, 0.0);
+        LightBounds {
+            power,
+            bounds: Some(Aabb::from_points(&p, &p)),
+        }
+    }
+
+    #[test]
+    fn test_bvh_pmf_matches_sample() {
+        let mut lights: Vec<LightBounds> = (0..37)
+            .map(|i| light_at(i as f64, 1.0 + (i % 3) as f64))
+            .collect();
+        lights.push(light_at(50.0, 0.0));
+        lights.push(LightBounds {
+            power: 0.0,
+            bounds: None,
+        });
+        let sampler = Bvh::new(&lights);
+        let point = Point::new(3.2, 1.0, 0.0);
+
+        let total: f64 = (0..lights.len()).map(|i| sampler.pmf(&point, i)).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        assert_eq!(0.0, sampler.pmf(&point, 37));
+        assert_eq!(0.5, sampler.pmf(&point, 38));
+
+        for i in 0..100 {
+            let u = (i as f64 + 0.5) / 100.0;
+            let (index, pmf) = sampler.sample(&point, u).unwrap();
+            assert!((pmf - sampler.pmf(&point, index)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_bvh_prefers_nearby_lights() {
+        let lights: Vec<LightBounds> = (0..16).map(|i| light_at(i as f64, 1.0)).collect();
+        let sampler = Bvh::new(&lights);
+        let point = Point::new(0.0, 0.5, 0.0);
+
+        assert!(sampler.pmf(&point, 0) > 10.0 * sampler.pmf(&point, 15));
+    }
+}
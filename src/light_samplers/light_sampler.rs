@@ -0,0 +1,104 @@
+use crate::{aabb::Aabb, point::Point, scene::Scene};
+
+/// Chooses which of a scene's light sources to sample for direct lighting. Light sources are
+/// indexed in the order the scene's area lights, its punctual lights, and then its environment
+/// (if it can be sampled) appear.
+pub trait LightSampler {
+    /// Picks a light source for shading `point`, returning its index and the probability of
+    /// picking it, or None if no light is worth sampling.
+    fn sample(&self, point: &Point, u: f64) -> Option<(usize, f64)>;
+
+    /// The probability with which `sample` picks light `index` for shading `point`.
+    fn pmf(&self, point: &Point, index: usize) -> f64;
+}
+
+/// What a light sampler knows about each light source.
+pub struct LightBounds {
+    pub power: f64,
+    /// None for lights infinitely far away.
+    pub bounds: Option<Aabb>,
+}
+
+impl LightBounds {
+    /// Describes each of the scene's light sources, in the order light samplers index them.
+    pub fn for_scene(scene: &Scene) -> Vec<LightBounds> {
+        let area = scene.lights.iter().map(|light| LightBounds {
+            power: light.power(),
+            bounds: Some(light.bounding_box()),
+        });
+        let punctual = scene.punctual_lights.iter().map(|light| LightBounds {
+            power: light.power(),
+            bounds: light.bounding_box(),
+        });
+        let environment = scene.environment.can_sample().then_some(LightBounds {
+            power: 0.0,
+            bounds: None,
+        });
+        area.chain(punctual).chain(environment).collect()
+    }
+}
+
+/// Lights infinitely far away can't be weighed against the others by power or distance, so they
+/// are each picked as often as all of the finite lights put together.
+pub struct InfiniteLights {
+    indices: Vec<usize>,
+    /// The probability of picking any one infinite light, or the finite lights as a group.
+    share: f64,
+    has_finite: bool,
+}
+
+/// The outcome of choosing between the infinite lights and the finite ones.
+pub enum Split {
+    /// The index of the infinite light picked.
+    Infinite(usize),
+    /// A finite light should be picked, using the remapped random number.
+    Finite(f64),
+}
+
+impl InfiniteLights {
+    /// `has_finite` says whether any of the finite lights can be picked at all.
+    pub fn new(lights: &[LightBounds], has_finite: bool) -> Self {
+        let indices: Vec<usize> = lights
+            .iter()
+            .enumerate()
+            .filter(|(_, light)| light.bounds.is_none())
+            .map(|(i, _)| i)
+            .collect();
+        let groups = indices.len() + has_finite as usize;
+        InfiniteLights {
+            indices,
+            share: if groups > 0 { 1.0 / groups as f64 } else { 0.0 },
+            has_finite,
+        }
+    }
+
+    /// The probability of picking any particular infinite light.
+    pub fn pmf(&self) -> f64 {
+        self.share
+    }
+
+    /// The probability of picking one of the finite lights, to be multiplied by the probability
+    /// of picking a particular one among them.
+    pub fn finite_probability(&self) -> f64 {
+        if self.has_finite {
+            self.share
+        } else {
+            0.0
+        }
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.indices.contains(&index)
+    }
+
+    pub fn split(&self, u: f64) -> Split {
+        let infinite_probability = self.indices.len() as f64 * self.share;
+        if u < infinite_probability {
+            let i = ((u / self.share) as usize).min(self.indices.len() - 1);
+            Split::Infinite(self.indices[i])
+        } else {
+            let u = (u - infinite_probability) / self.share;
+            Split::Finite(u.min(1.0 - f64::EPSILON))
+        }
+    }
+}
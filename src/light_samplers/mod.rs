@@ -0,0 +1,4 @@
+pub mod bvh;
+pub mod light_sampler;
+pub mod power;
+pub mod uniform;
@@ -0,0 +1,57 @@
+use crate::{distribution::AliasTable, point::Point};
+
+use super::light_sampler::{InfiniteLights, LightBounds, LightSampler, Split};
+
+/// Picks finite lights in proportion to the power they emit, wherever the point being shaded is.
+/// If none of them emit any, they're picked uniformly instead.
+pub struct Power {
+    infinite: InfiniteLights,
+    /// Indices of the finite lights, in the order the table holds them.
+    finite: Vec<usize>,
+    table: Option<AliasTable>,
+    pmfs: Vec<f64>,
+}
+
+impl Power {
+    pub fn new(lights: &[LightBounds]) -> Self {
+        let finite: Vec<usize> = (0..lights.len())
+            .filter(|i| lights[*i].bounds.is_some())
+            .collect();
+        let powers: Vec<f64> = finite.iter().map(|i| lights[*i].power.max(0.0)).collect();
+        let table = (!finite.is_empty()).then(|| AliasTable::new(&powers));
+        let infinite = InfiniteLights::new(lights, table.is_some());
+
+        let mut pmfs = vec![0.0; lights.len()];
+        for (i, pmf) in pmfs.iter_mut().enumerate() {
+            if infinite.contains(i) {
+                *pmf = infinite.pmf();
+            }
+        }
+        if let Some(table) = &table {
+            for (j, i) in finite.iter().enumerate() {
+                pmfs[*i] = table.pmf(j) * infinite.finite_probability();
+            }
+        }
+
+        Power {
+            infinite,
+            finite,
+            table,
+            pmfs,
+        }
+    }
+}
+
+impl LightSampler for Power {
+    fn sample(&self, _: &Point, u: f64) -> Option<(usize, f64)> {
+        let index = match self.infinite.split(u) {
+            Split::Infinite(index) => index,
+            Split::Finite(u) => self.finite[self.table.as_ref()?.sample(u).0],
+        };
+        Some((index, self.pmfs[index]))
+    }
+
+    fn pmf(&self, _: &Point, index: usize) -> f64 {
+        self.pmfs.get(index).copied().unwrap_or(0.0)
+    }
+}
@@ -0,0 +1,32 @@
+use crate::point::Point;
+
+use super::light_sampler::LightSampler;
+
+/// Picks every light with the same probability.
+pub struct Uniform {
+    count: usize,
+}
+
+impl Uniform {
+    pub fn new(count: usize) -> Self {
+        Uniform { count }
+    }
+}
+
+impl LightSampler for Uniform {
+    fn sample(&self, _: &Point, u: f64) -> Option<(usize, f64)> {
+        if self.count == 0 {
+            return None;
+        }
+        let index = ((u * self.count as f64) as usize).min(self.count - 1);
+        Some((index, 1.0 / self.count as f64))
+    }
+
+    fn pmf(&self, _: &Point, _: usize) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            1.0 / self.count as f64
+        }
+    }
+}
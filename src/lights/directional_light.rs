@@ -0,0 +1,45 @@
+use crate::{aabb::Aabb, color::Color, description::Description, point::Point, vec3::Vec3};
+
+use super::light::{Light, LightSample};
+
+/// A light infinitely far away, arriving from the same direction everywhere, like the sun.
+pub struct DirectionalLight {
+    /// The direction the light travels in.
+    direction: Vec3,
+    /// Irradiance on a surface facing the light.
+    irradiance: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: &Vec3, irradiance: Color) -> Self {
+        DirectionalLight {
+            direction: direction.unit(),
+            irradiance,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _: &Point) -> Option<LightSample> {
+        Some(LightSample {
+            wi: -self.direction.clone(),
+            radiance: self.irradiance.clone(),
+            distance: f64::INFINITY,
+        })
+    }
+
+    fn power(&self) -> f64 {
+        f64::INFINITY // spread over an infinite area
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("directional light")
+            .vec3(&self.direction)
+            .vec3(&self.irradiance);
+    }
+}
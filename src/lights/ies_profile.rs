@@ -0,0 +1,329 @@
+use std::{fs, io, path::Path};
+
+use crate::{description::Description, onb::Onb, vec3::Vec3};
+
+/// A goniometric intensity distribution read from an IESNA LM-63 photometric file. Only type C
+/// photometry, by far the most common for architectural fixtures, is supported.
+///
+/// Directions are measured in the fixture's local space, where +z is the nadir (a vertical angle
+/// of 0°) and +x and +y are the horizontal angles 0° and 90°.
+///
+/// Lights use the profile's shape, normalised so that its brightest direction is 1, and take
+/// their brightness from the intensity they're given. Pass `max_candela` as that intensity to
+/// render the fixture as bright as it was measured, with scene units taken as candela.
+pub struct IesProfile {
+    /// Vertical angles in degrees, ascending.
+    vertical_angles: Vec<f64>,
+    /// Horizontal angles in degrees, ascending from 0°.
+    horizontal_angles: Vec<f64>,
+    /// Candela values for each horizontal angle in turn, over all vertical angles.
+    candela: Vec<f64>,
+    max_candela: f64,
+}
+
+impl IesProfile {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        IesProfile::parse(&String::from_utf8_lossy(&bytes))
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        // keyword lines run up to the TILT line, after which everything is whitespace separated
+        // numbers, regardless of how they're broken across lines
+        let mut lines = text.lines();
+        let tilt = loop {
+            let line = lines
+                .next()
+                .ok_or_else(|| invalid_data("missing TILT line".to_string()))?;
+            if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+                break tilt.trim().to_string();
+            }
+        };
+        let rest: Vec<&str> = lines.flat_map(|line| line.split_whitespace()).collect();
+        let mut numbers = Numbers {
+            tokens: rest.iter(),
+        };
+
+        match tilt.as_str() {
+            "NONE" => {}
+            "INCLUDE" => {
+                // lamp to luminaire geometry, then the tilt angles and their multipliers, which
+                // only matter for lamps mounted at an angle
+                numbers.next()?;
+                let count = numbers.count()?;
+                for _ in 0..2 * count {
+                    numbers.next()?;
+                }
+            }
+            file => {
+                return Err(invalid_data(format!("unsupported tilt file: {}", file)));
+            }
+        }
+
+        let _lamp_count = numbers.next()?;
+        let _lumens_per_lamp = numbers.next()?;
+        let multiplier = numbers.next()?;
+        let vertical_count = numbers.count()?;
+        let horizontal_count = numbers.count()?;
+        let photometric_type = numbers.next()?;
+        let _units = numbers.next()?;
+        let _dimensions = [numbers.next()?, numbers.next()?, numbers.next()?];
+        let ballast_factor = numbers.next()?;
+        let ballast_lamp_factor = numbers.next()?;
+        let _input_watts = numbers.next()?;
+
+        if photometric_type != 1.0 {
+            return Err(invalid_data(format!(
+                "unsupported photometric type: {}",
+                photometric_type
+            )));
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(invalid_data("profile has no angles".to_string()));
+        }
+
+        let vertical_angles = numbers.take(vertical_count)?;
+        let horizontal_angles = numbers.take(horizontal_count)?;
+        let scale = multiplier * ballast_factor * ballast_lamp_factor;
+        let candela: Vec<f64> = numbers
+            .take(vertical_count * horizontal_count)?
+            .into_iter()
+            .map(|c| c * scale)
+            .collect();
+
+        if !is_ascending(&vertical_angles) || !is_ascending(&horizontal_angles) {
+            return Err(invalid_data("angles must be ascending".to_string()));
+        }
+        if horizontal_angles[0] != 0.0 || *horizontal_angles.last().unwrap() > 360.0 {
+            return Err(invalid_data(
+                "horizontal angles must run from 0 to at most 360 degrees".to_string(),
+            ));
+        }
+        if vertical_angles[0] < 0.0 || *vertical_angles.last().unwrap() > 180.0 {
+            return Err(invalid_data(
+                "vertical angles must lie between 0 and 180 degrees".to_string(),
+            ));
+        }
+        if candela.iter().any(|c| *c < 0.0 || !c.is_finite()) {
+            return Err(invalid_data(
+                "candela values must be non-negative".to_string(),
+            ));
+        }
+
+        let max_candela = candela.iter().cloned().fold(0.0, f64::max);
+        Ok(IesProfile {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+            max_candela,
+        })
+    }
+
+    /// The brightest intensity in the profile, in candela. This is the factor `intensity`
+    /// normalises by, so lights shaped by the profile can be given it to emit the fixture's
+    /// measured intensity.
+    pub fn max_candela(&self) -> f64 {
+        self.max_candela
+    }
+
+    /// The intensity towards the local direction `w`, in candela, including the file's multiplier
+    /// and ballast factors.
+    pub fn candela(&self, w: &Vec3) -> f64 {
+        let w = w.unit();
+        let vertical = w.z().clamp(-1.0, 1.0).acos().to_degrees();
+        let horizontal = w.y().atan2(w.x()).to_degrees().rem_euclid(360.0);
+        self.candela_at(vertical, horizontal)
+    }
+
+    /// The intensity towards the local direction `w` relative to the brightest direction, in
+    /// `[0, 1]`. This is `candela` divided by `max_candela`, leaving only the shape of the
+    /// distribution.
+    pub fn intensity(&self, w: &Vec3) -> f64 {
+        if self.max_candela <= 0.0 {
+            return 0.0;
+        }
+        self.candela(w) / self.max_candela
+    }
+
+    /// Bilinearly interpolates the candela values at the given angles, in degrees.
+    fn candela_at(&self, vertical: f64, horizontal: f64) -> f64 {
+        let last_vertical = *self.vertical_angles.last().unwrap();
+        if vertical < self.vertical_angles[0] || vertical > last_vertical {
+            return 0.0;
+        }
+
+        // profiles only store as much of the distribution as its symmetry requires
+        let last_horizontal = *self.horizontal_angles.last().unwrap();
+        let horizontal = if last_horizontal == 0.0 {
+            0.0 // rotationally symmetric
+        } else if last_horizontal == 90.0 {
+            let h = if horizontal > 180.0 {
+                360.0 - horizontal
+            } else {
+                horizontal
+            };
+            if h > 90.0 {
+                180.0 - h
+            } else {
+                h
+            }
+        } else if last_horizontal == 180.0 && horizontal > 180.0 {
+            360.0 - horizontal
+        } else {
+            horizontal
+        };
+
+        let (v0, v1, tv) = bracket(&self.vertical_angles, vertical, None);
+        let wrap = (last_horizontal > 180.0).then_some(360.0);
+        let (h0, h1, th) = bracket(&self.horizontal_angles, horizontal, wrap);
+
+        let n = self.vertical_angles.len();
+        let c = |h: usize, v: usize| self.candela[h * n + v];
+        let near = c(h0, v0) * (1.0 - tv) + c(h0, v1) * tv;
+        let far = c(h1, v0) * (1.0 - tv) + c(h1, v1) * tv;
+        near * (1.0 - th) + far * th
+    }
+}
+
+/// Adds a light's profile, if it has one, and the frame orienting it to `description`.
+pub fn describe_profile(profile: Option<&(&IesProfile, Onb)>, description: &mut Description) {
+    let Some((profile, onb)) = profile else {
+        description.u64(0);
+        return;
+    };
+    description
+        .u64(1)
+        .f64s(&profile.vertical_angles)
+        .f64s(&profile.horizontal_angles)
+        .f64s(&profile.candela)
+        .vec3(&onb.u)
+        .vec3(&onb.v)
+        .vec3(&onb.w);
+}
+
+/// Finds the pair of `angles` around `x`, and how far `x` is between them. With `wrap` set, `x`
+/// past the last angle is interpolated towards the first angle plus `wrap`.
+fn bracket(angles: &[f64], x: f64, wrap: Option<f64>) -> (usize, usize, f64) {
+    let last = angles.len() - 1;
+    if x >= angles[last] {
+        return match wrap {
+            Some(period) if angles[last] < period => {
+                let t = (x - angles[last]) / (angles[0] + period - angles[last]);
+                (last, 0, t)
+            }
+            _ => (last, last, 0.0),
+        };
+    }
+    let i = angles.partition_point(|a| *a <= x).saturating_sub(1);
+    let t = (x - angles[i]) / (angles[i + 1] - angles[i]);
+    (i, i + 1, t.clamp(0.0, 1.0))
+}
+
+fn is_ascending(values: &[f64]) -> bool {
+    values.windows(2).all(|w| w[0] < w[1])
+}
+
+struct Numbers<'a, 'b> {
+    tokens: std::slice::Iter<'b, &'a str>,
+}
+
+impl Numbers<'_, '_> {
+    fn next(&mut self) -> io::Result<f64> {
+        let token = self
+            .tokens
+            .next()
+            .ok_or_else(|| invalid_data("profile ended early".to_string()))?;
+        token
+            .parse()
+            .map_err(|_| invalid_data(format!("expected a number, found {}", token)))
+    }
+
+    /// Reads a number that must be a whole, non-negative count.
+    fn count(&mut self) -> io::Result<usize> {
+        let value = self.next()?;
+        if value < 0.0 || value.fract() != 0.0 {
+            return Err(invalid_data(format!("expected a count, found {}", value)));
+        }
+        Ok(value as usize)
+    }
+
+    fn take(&mut self, n: usize) -> io::Result<Vec<f64>> {
+        (0..n).map(|_| self.next()).collect()
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILE: &str = "IESNA:LM-63-2002
+[TEST] bilateral test fixture
+[MANUFAC] nobody
+TILT=NONE
+1 1000 2.0 3 2 1 2 0.1 0.1 0.0
+1.0 1.0 10
+0 45 90
+0 180
+100 50 0
+100 150
+0
+";
+
+    #[test]
+    fn test_parse_and_interpolate() {
+        let profile = IesProfile::parse(PROFILE).unwrap();
+        assert_eq!(300.0, profile.max_candela());
+        // the multiplier of 2 is applied to the absolute values
+        assert!((profile.candela(&Vec3::new(0.0, 0.0, 1.0)) - 200.0).abs() < 1e-12);
+
+        // straight down, where every horizontal angle meets
+        let nadir = Vec3::new(0.0, 0.0, 1.0);
+        assert!((profile.intensity(&nadir) - 2.0 / 3.0).abs() < 1e-12);
+
+        // halfway between 0 and 45 degrees down, towards 0 and 180 degrees around
+        let theta = 22.5f64.to_radians();
+        let towards_0 = Vec3::new(theta.sin(), 0.0, theta.cos());
+        let towards_180 = Vec3::new(-theta.sin(), 0.0, theta.cos());
+        assert!((profile.intensity(&towards_0) - 0.5).abs() < 1e-12);
+        assert!((profile.intensity(&towards_180) - 5.0 / 6.0).abs() < 1e-12);
+
+        // bilateral symmetry mirrors 270 degrees onto 90 degrees
+        let towards_90 = Vec3::new(0.0, theta.sin(), theta.cos());
+        let towards_270 = Vec3::new(0.0, -theta.sin(), theta.cos());
+        assert!((profile.intensity(&towards_90) - profile.intensity(&towards_270)).abs() < 1e-12);
+
+        // above the last vertical angle
+        assert_eq!(0.0, profile.intensity(&Vec3::new(0.0, 0.0, -1.0)));
+    }
+
+    #[test]
+    fn test_parse_tilt_include() {
+        let text = PROFILE.replace("TILT=NONE", "TILT=INCLUDE\n1\n2\n0 90\n1.0 0.8");
+
+        assert!(IesProfile::parse(&text).is_ok());
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        let missing_tilt = PROFILE.replace("TILT=NONE", "");
+        let truncated = PROFILE.trim_end().trim_end_matches('0');
+        let descending = PROFILE.replace("0 45 90", "0 90 45");
+        let not_a_number = PROFILE.replace("100 150", "100 abc");
+        let type_a = PROFILE.replace("3 2 1 2", "3 2 3 2");
+
+        for text in [
+            missing_tilt.as_str(),
+            truncated,
+            &descending,
+            &not_a_number,
+            &type_a,
+        ] {
+            let res = IesProfile::parse(text);
+            assert_eq!(io::ErrorKind::InvalidData, res.err().unwrap().kind());
+        }
+    }
+}
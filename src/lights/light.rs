@@ -0,0 +1,28 @@
+use crate::{aabb::Aabb, color::Color, description::Description, point::Point, vec3::Vec3};
+
+/// Illumination arriving at a point from a light, along a single direction.
+pub struct LightSample {
+    /// Unit direction from the point towards the light.
+    pub wi: Vec3,
+    /// The radiance arriving along `wi`, already integrated over the light's (vanishing) extent.
+    pub radiance: Color,
+    /// How far along `wi` the light is, or infinity for lights infinitely far away.
+    pub distance: f64,
+}
+
+/// A light described by a delta distribution, such as an infinitely small point. Since no ray can
+/// hit them, they are only reached by sampling them directly.
+pub trait Light {
+    /// The illumination the light casts at `point`, if any, ignoring anything in between.
+    fn sample(&self, point: &Point) -> Option<LightSample>;
+
+    /// A rough estimate of the total power the light emits, for deciding how often to sample it.
+    /// Lights without a bounding box are picked separately, so needn't give a meaningful power.
+    fn power(&self) -> f64;
+
+    /// A box enclosing the light, or None if it's infinitely far away.
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    /// Adds the kind of light, where it is and what it emits to `description`.
+    fn describe(&self, description: &mut Description);
+}
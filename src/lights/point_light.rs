@@ -0,0 +1,119 @@
+use std::f64::consts::PI;
+
+use crate::{
+    aabb::Aabb,
+    color::{luminance, Color},
+    description::Description,
+    onb::Onb,
+    point::Point,
+};
+
+use super::{
+    ies_profile::{describe_profile, IesProfile},
+    light::{Light, LightSample},
+};
+
+/// A light radiating from a single point, equally in every direction unless shaped by a
+/// photometric profile.
+pub struct PointLight<'a> {
+    position: Point,
+    /// Radiant intensity, the power per unit solid angle.
+    intensity: Color,
+    /// The profile and the frame it's oriented by.
+    profile: Option<(&'a IesProfile, Onb)>,
+}
+
+impl<'a> PointLight<'a> {
+    pub fn new(position: Point, intensity: Color) -> Self {
+        PointLight {
+            position,
+            intensity,
+            profile: None,
+        }
+    }
+
+    /// A light whose intensity varies with direction according to `profile`, scaled so that its
+    /// brightest direction emits `intensity`, which can be `profile.max_candela()` to match the
+    /// fixture's measurements. The profile's nadir points along `orientation.w`, and its 0°
+    /// horizontal angle along `orientation.u`.
+    pub fn with_profile(
+        position: Point,
+        intensity: Color,
+        profile: &'a IesProfile,
+        orientation: Onb,
+    ) -> Self {
+        PointLight {
+            position,
+            intensity,
+            profile: Some((profile, orientation)),
+        }
+    }
+}
+
+impl Light for PointLight<'_> {
+    fn sample(&self, point: &Point) -> Option<LightSample> {
+        let to_light = &self.position - point;
+        let distance_sq = to_light.len_sq();
+        if distance_sq == 0.0 {
+            return None;
+        }
+        let wi = to_light.unit();
+        let scale = match &self.profile {
+            Some((profile, orientation)) => profile.intensity(&orientation.to_local(&-wi.clone())),
+            None => 1.0,
+        };
+        if scale <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi,
+            radiance: &self.intensity * (scale / distance_sq),
+            distance: distance_sq.sqrt(),
+        })
+    }
+
+    fn power(&self) -> f64 {
+        // a profile only ever dims the light, so this is an upper bound
+        4.0 * PI * luminance(&self.intensity)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.position, &self.position))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("point light")
+            .vec3(&self.position)
+            .vec3(&self.intensity);
+        describe_profile(self.profile.as_ref(), description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Vec3;
+
+    #[test]
+    fn test_point_light_profile_orientation() {
+        // a fixture that only shines at horizontal angles near 0 degrees, from nadir to horizon
+        let text = "TILT=NONE\n1 -1 1 2 3 1 2 0 0 0\n1 1 0\n0 90\n0 90 180\n10 10 0 0 0 0";
+        let profile = IesProfile::parse(text).unwrap();
+        let down = Onb::with_tangent(&Vec3::new(0.0, -1.0, 0.0), &Vec3::new(0.0, 0.0, -1.0));
+        let light = PointLight::with_profile(
+            Point::new(0.0, 1.0, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+            &profile,
+            down,
+        );
+
+        let below = light.sample(&Point::new(0.0, 0.0, 0.0)).unwrap();
+        assert_eq!(1.0, below.radiance.x());
+
+        // towards the 0 degree horizontal angle, and away from it
+        let ahead = light.sample(&Point::new(0.0, 1.0, -1.0)).unwrap();
+        assert_eq!(1.0, ahead.radiance.x());
+        assert!(light.sample(&Point::new(0.0, 1.0, 1.0)).is_none());
+    }
+}
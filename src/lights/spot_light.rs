@@ -0,0 +1,170 @@
+use std::f64::consts::PI;
+
+use crate::{
+    aabb::Aabb,
+    color::{luminance, Color},
+    deg_to_radians,
+    description::Description,
+    onb::Onb,
+    point::Point,
+    vec3::{dot, Vec3},
+};
+
+use super::{
+    ies_profile::{describe_profile, IesProfile},
+    light::{Light, LightSample},
+};
+
+/// A point light that only shines within a cone. Its intensity is full inside the inner cone and
+/// falls off smoothly to nothing at the outer cone.
+pub struct SpotLight<'a> {
+    position: Point,
+    direction: Vec3,
+    intensity: Color,
+    cos_inner: f64,
+    cos_outer: f64,
+    /// The profile and the frame it's oriented by.
+    profile: Option<(&'a IesProfile, Onb)>,
+}
+
+impl<'a> SpotLight<'a> {
+    /// `inner_angle` and `outer_angle` are the cones' half angles, in degrees.
+    pub fn new(
+        position: Point,
+        direction: &Vec3,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+    ) -> Self {
+        let outer_angle = outer_angle.clamp(0.0, 180.0);
+        let inner_angle = inner_angle.clamp(0.0, outer_angle);
+        SpotLight {
+            position,
+            direction: direction.unit(),
+            intensity,
+            cos_inner: deg_to_radians(inner_angle).cos(),
+            cos_outer: deg_to_radians(outer_angle).cos(),
+            profile: None,
+        }
+    }
+
+    /// A spot light further shaped by `profile`, scaled so that the profile's brightest direction
+    /// emits `intensity`, which can be `profile.max_candela()` to match the fixture's
+    /// measurements. The spot and the profile's nadir point along `orientation.w`, and the
+    /// profile's 0° horizontal angle along `orientation.u`.
+    pub fn with_profile(
+        position: Point,
+        intensity: Color,
+        inner_angle: f64,
+        outer_angle: f64,
+        profile: &'a IesProfile,
+        orientation: Onb,
+    ) -> Self {
+        let spot = SpotLight::new(
+            position,
+            &orientation.w,
+            intensity,
+            inner_angle,
+            outer_angle,
+        );
+        SpotLight {
+            profile: Some((profile, orientation)),
+            ..spot
+        }
+    }
+
+    /// The fraction of the full intensity emitted in direction `w`, away from the light.
+    fn falloff(&self, w: &Vec3) -> f64 {
+        let cos_theta = dot(w, &self.direction);
+        let cone = if self.cos_inner <= self.cos_outer {
+            if cos_theta >= self.cos_outer {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            smoothstep(self.cos_outer, self.cos_inner, cos_theta)
+        };
+        match &self.profile {
+            Some((profile, orientation)) if cone > 0.0 => {
+                cone * profile.intensity(&orientation.to_local(w))
+            }
+            _ => cone,
+        }
+    }
+}
+
+impl Light for SpotLight<'_> {
+    fn sample(&self, point: &Point) -> Option<LightSample> {
+        let to_light = &self.position - point;
+        let distance_sq = to_light.len_sq();
+        if distance_sq == 0.0 {
+            return None;
+        }
+        let wi = to_light.unit();
+        let falloff = self.falloff(&-wi.clone());
+        if falloff <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            wi,
+            radiance: &self.intensity * (falloff / distance_sq),
+            distance: distance_sq.sqrt(),
+        })
+    }
+
+    fn power(&self) -> f64 {
+        // the solid angle of a cone halfway between the inner and outer ones
+        let solid_angle = 2.0 * PI * (1.0 - (self.cos_inner + self.cos_outer) / 2.0);
+        solid_angle * luminance(&self.intensity)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points(&self.position, &self.position))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("spot light")
+            .vec3(&self.position)
+            .vec3(&self.direction)
+            .vec3(&self.intensity)
+            .f64(self.cos_inner)
+            .f64(self.cos_outer);
+        describe_profile(self.profile.as_ref(), description);
+    }
+}
+
+/// Hermite interpolation from 0 at `edge0` to 1 at `edge1`, with zero slope at both ends.
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spot_light_falloff() {
+        let light = SpotLight::new(
+            Point::new(0.0, 2.0, 0.0),
+            &Vec3::new(0.0, -1.0, 0.0),
+            Color::new(4.0, 4.0, 4.0),
+            20.0,
+            40.0,
+        );
+
+        let inside = light.sample(&Point::new(0.0, 0.0, 0.0)).unwrap();
+        assert_eq!(1.0, inside.radiance.x());
+        assert_eq!(2.0, inside.distance);
+
+        // 30 degrees off axis is halfway between the cones in angle, but not in cosine
+        let x = 2.0 * deg_to_radians(30.0).tan();
+        let between = light.sample(&Point::new(x, 0.0, 0.0)).unwrap();
+        let res = between.radiance.x() * (x * x + 4.0) / 4.0;
+        assert!(0.0 < res && res < 1.0);
+
+        assert!(light.sample(&Point::new(4.0, 0.0, 0.0)).is_none());
+    }
+}
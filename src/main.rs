@@ -1,26 +1,125 @@
-mod camera;
-mod color;
-mod hit_record;
-mod hittable;
-mod interval;
-mod materials;
-mod point;
-mod ray;
-mod sphere;
-mod vec3;
-
-use camera::Camera;
-use color::Color;
-use hittable::Hittable;
-use materials::lambertian::Lambertian;
-use materials::metal::Metal;
-use point::Point;
-use sphere::Sphere;
-use std::fs::File;
-use std::io::Write;
-
-fn main() {
+use ray_tracing::camera::Camera;
+use ray_tracing::checkpoint::Checkpoint;
+use ray_tracing::color::Color;
+use ray_tracing::environments::sky::Sky;
+use ray_tracing::environments::sun::sun_direction;
+use ray_tracing::hittable::Hittable;
+use ray_tracing::integrator::Integrator;
+use ray_tracing::materials::lambertian::Lambertian;
+use ray_tracing::materials::metal::Metal;
+use ray_tracing::point::Point;
+use ray_tracing::progressive::{Progressive, StopReason};
+use ray_tracing::samplers::independent::Independent;
+use ray_tracing::scene::Scene;
+use ray_tracing::sphere::Sphere;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const USAGE: &str = "usage: ray-tracing [options]
+  --spp <n>          samples per pixel to render (default 100)
+  --pass <n>         samples per pixel in each pass, after which the image is written out
+                     (default: all of them in one pass)
+  --time <seconds>   stop once this much time has passed
+  --seed <n>         seed for the random numbers (default 0)
+  --output <path>    where to write the image (default image.ppm)
+  --checkpoint <path>
+                     where to write a checkpoint after each pass, to resume from later
+  --resume <path>    carry on from a checkpoint, to the samples per pixel and with the seed it
+                     was started with, writing checkpoints back to it unless --checkpoint is given";
+
+struct Options {
+    samples_per_pixel: Option<usize>,
+    pass_samples: Option<usize>,
+    time_budget: Option<Duration>,
+    seed: Option<u64>,
+    output: String,
+    checkpoint: Option<String>,
+    resume: Option<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            samples_per_pixel: None,
+            pass_samples: None,
+            time_budget: None,
+            seed: None,
+            output: "image.ppm".to_string(),
+            checkpoint: None,
+            resume: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "--spp" => options.samples_per_pixel = Some(parse_number(&arg, &value()?)?),
+                "--pass" => options.pass_samples = Some(parse_number(&arg, &value()?)?),
+                "--time" => {
+                    let seconds: f64 = parse_number(&arg, &value()?)?;
+                    let budget = Duration::try_from_secs_f64(seconds)
+                        .map_err(|_| format!("invalid time: {}", seconds))?;
+                    options.time_budget = Some(budget);
+                }
+                "--seed" => options.seed = Some(parse_number(&arg, &value()?)?),
+                "--output" => options.output = value()?,
+                "--checkpoint" => options.checkpoint = Some(value()?),
+                "--resume" => options.resume = Some(value()?),
+                _ => return Err(format!("unknown option: {}", arg)),
+            }
+        }
+        let fixed_by_checkpoint = options.samples_per_pixel.is_some() || options.seed.is_some();
+        if options.resume.is_some() && fixed_by_checkpoint {
+            return Err("--spp and --seed come from the checkpoint when resuming".to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", option, value))
+}
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // stop at the next row on Ctrl-C, and write out what there is so far
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_interrupted = interrupted.clone();
+    if let Err(error) =
+        ctrlc::set_handler(move || handler_interrupted.store(true, Ordering::Relaxed))
+    {
+        eprintln!("couldn't handle Ctrl-C: {}", error);
+    }
+
+    let checkpoint = match &options.resume {
+        Some(path) => match Checkpoint::load(path) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(error) => {
+                eprintln!("couldn't load checkpoint {}: {}", path, error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+    let (samples_per_pixel, seed) = match &checkpoint {
+        Some(checkpoint) => (checkpoint.samples_per_pixel, checkpoint.seed),
+        None => (
+            options.samples_per_pixel.unwrap_or(100),
+            options.seed.unwrap_or(0),
+        ),
+    };
+
     let mut camera = Camera::default();
+    camera.sampler = Box::new(Independent::new(samples_per_pixel, seed));
 
     let material_floor = Lambertian::new(Color::new(0.8, 0.8, 0.0));
     let material_centre = Lambertian::new(Color::new(0.1, 0.2, 0.5));
@@ -33,13 +132,35 @@ fn main() {
     let right = Sphere::new(Point::new(1.0, 0.0, -1.0), 0.5, &material_right);
     let hittables: [&dyn Hittable; 4] = [&floor, &centre, &left, &right];
 
-    let reflection_depth = 50;
-    let ppm = camera.render(&hittables, reflection_depth);
-
-    let mut file = File::create("image.ppm").unwrap();
-    file.write_all(ppm.as_bytes()).unwrap();
-}
+    // a late spring afternoon in London
+    let sun = sun_direction(51.5, -0.1, 0.0, 140.0, 16.0);
+    let sky = Sky::new(&sun, 2.5, Color::new(0.2, 0.2, 0.2), 0.53, 0.02);
+    let scene = Scene::new(&hittables, &[], &sky);
+    let integrator = Integrator::default();
+    if let Some(checkpoint) = checkpoint {
+        if let Err(error) = camera.resume(checkpoint, &scene, &integrator) {
+            eprintln!("can't resume: {}", error);
+            return ExitCode::FAILURE;
+        }
+    }
 
-fn deg_to_radians(degrees: f64) -> f64 {
-    degrees * std::f64::consts::PI / 180.0
+    let pass_samples = options.pass_samples.unwrap_or(samples_per_pixel);
+    let mut progressive = Progressive::new(pass_samples, &options.output);
+    progressive.time_budget = options.time_budget;
+    progressive.checkpoint_path = options.checkpoint.or(options.resume).map(Into::into);
+    match camera.render_progressive(&scene, &integrator, &progressive, &interrupted) {
+        Ok(reason) => {
+            let spp = camera.film().min_sample_count();
+            match reason {
+                StopReason::Finished => println!("finished at {} spp", spp),
+                StopReason::OutOfTime => println!("out of time at {} spp", spp),
+                StopReason::Interrupted => println!("interrupted at {} spp", spp),
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("couldn't save the render: {}", error);
+            ExitCode::FAILURE
+        }
+    }
 }
@@ -0,0 +1,37 @@
+use crate::{color::Color, description::Description, hit_record::HitRecord, vec3::Vec3};
+
+use super::material::{BsdfSample, Material};
+
+/// An emitter that gives off the same radiance in every direction, from both sides of the
+/// surface. It doesn't reflect any light.
+pub struct DiffuseLight {
+    emit: Color,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color) -> Self {
+        DiffuseLight { emit }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn eval(&self, _: &Vec3, _: &Vec3, _: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    fn pdf(&self, _: &Vec3, _: &Vec3, _: &HitRecord) -> f64 {
+        0.0
+    }
+
+    fn sample(&self, _: &Vec3, _: &HitRecord, _: [f64; 3]) -> Option<BsdfSample> {
+        None
+    }
+
+    fn emitted(&self) -> Color {
+        self.emit.clone()
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("diffuse light").vec3(&self.emit);
+    }
+}
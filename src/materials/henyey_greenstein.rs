@@ -0,0 +1,129 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    description::Description,
+    hit_record::HitRecord,
+    interval::Interval,
+    onb::Onb,
+    vec3::{dot, Vec3},
+};
+
+use super::material::{BsdfSample, Material};
+
+/// The Henyey-Greenstein phase function, for media that scatter light preferentially forwards
+/// (`g > 0`), like clouds, or backwards (`g < 0`). `g = 0` is isotropic.
+pub struct HenyeyGreenstein {
+    albedo: Color,
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    /// `g` is the asymmetry parameter: the mean cosine of the scattering angle.
+    pub fn new(albedo: Color, g: f64) -> Self {
+        HenyeyGreenstein {
+            albedo,
+            g: Interval::new(-0.99, 0.99).clamp(g),
+        }
+    }
+
+    /// The phase function for a scattering angle with the given cosine.
+    fn phase(&self, cos_theta: f64) -> f64 {
+        let g = self.g;
+        let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, _: &HitRecord) -> Color {
+        // the ray travelled along -wo, so going on along wi scatters it by the angle between them
+        let cos_theta = -dot(&wo.unit(), &wi.unit());
+        &self.albedo * self.phase(cos_theta)
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, _: &HitRecord) -> f64 {
+        self.phase(-dot(&wo.unit(), &wi.unit()))
+    }
+
+    fn sample(&self, wo: &Vec3, hit: &HitRecord, u: [f64; 3]) -> Option<BsdfSample> {
+        // invert the CDF of the scattering angle
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u[1]
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u[1]);
+            (1.0 + g * g - s * s) / (2.0 * g)
+        }
+        .clamp(-1.0, 1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u[2];
+
+        let onb = Onb::new(&-wo.unit());
+        let wi = onb.to_world(&Vec3::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ));
+        Some(BsdfSample {
+            f: self.eval(wo, &wi, hit),
+            pdf: self.pdf(wo, &wi, hit),
+            wi,
+            is_delta: false,
+        })
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("henyey-greenstein")
+            .vec3(&self.albedo)
+            .f64(self.g);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::isotropic::Isotropic, point::Point};
+
+    #[test]
+    fn test_phase_normalised() {
+        let n = 100_000;
+        for g in [-0.7, 0.0, 0.3, 0.9] {
+            let hg = HenyeyGreenstein::new(Color::new(1.0, 1.0, 1.0), g);
+            // integrate over cos(theta) in [-1, 1], with 2 pi for the azimuth
+            let d_cos = 2.0 / n as f64;
+            let total: f64 = (0..n)
+                .map(|i| hg.phase(-1.0 + (i as f64 + 0.5) * d_cos) * 2.0 * PI * d_cos)
+                .sum();
+
+            assert!((total - 1.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_sample_mean_cosine_is_g() {
+        let g = 0.6;
+        let hg = HenyeyGreenstein::new(Color::new(1.0, 1.0, 1.0), g);
+        let isotropic = Isotropic::new(Color::new(1.0, 1.0, 1.0));
+        let hit = HitRecord::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            1.0,
+            true,
+            &isotropic,
+        );
+        let wo = Vec3::new(0.0, 0.0, -1.0);
+
+        let n = 1000;
+        let mut mean_cos = 0.0;
+        for i in 0..n {
+            let u = [0.0, (i as f64 + 0.5) / n as f64, 0.25];
+            let sample = hg.sample(&wo, &hit, u).unwrap();
+            mean_cos += -dot(&wo, &sample.wi);
+        }
+        mean_cos /= n as f64;
+
+        assert!((mean_cos - g).abs() < 1e-3);
+    }
+}
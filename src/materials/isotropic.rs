@@ -0,0 +1,47 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    description::Description,
+    hit_record::HitRecord,
+    pdf::{Pdf, SpherePdf},
+    vec3::Vec3,
+};
+
+use super::material::{BsdfSample, Material};
+
+/// The phase function of a participating medium that scatters light equally in all directions.
+/// Phase functions have no cosine term, so `eval` is just the albedo spread over the sphere.
+pub struct Isotropic {
+    albedo: Color,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Isotropic { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn eval(&self, _: &Vec3, _: &Vec3, _: &HitRecord) -> Color {
+        &self.albedo / (4.0 * PI)
+    }
+
+    fn pdf(&self, _: &Vec3, wi: &Vec3, _: &HitRecord) -> f64 {
+        SpherePdf.value(wi)
+    }
+
+    fn sample(&self, wo: &Vec3, hit: &HitRecord, u: [f64; 3]) -> Option<BsdfSample> {
+        let wi = SpherePdf.generate([u[1], u[2]]);
+        Some(BsdfSample {
+            f: self.eval(wo, &wi, hit),
+            pdf: self.pdf(wo, &wi, hit),
+            wi,
+            is_delta: false,
+        })
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("isotropic").vec3(&self.albedo);
+    }
+}
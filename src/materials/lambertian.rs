@@ -1,6 +1,12 @@
-use crate::{color::Color, hit_record::HitRecord, point::Point, ray::Ray, vec3::Vec3};
+use crate::{
+    color::Color,
+    description::Description,
+    hit_record::HitRecord,
+    pdf::{CosinePdf, Pdf},
+    vec3::{dot, Vec3},
+};
 
-use super::material::Material;
+use super::material::{BsdfSample, Material};
 
 pub struct Lambertian {
     albedo: Color,
@@ -13,15 +19,94 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _: &Ray, hit: &HitRecord) -> (Ray, Color) {
-        let mut scatter_direction = &hit.normal + &Vec3::random_unit();
-        if scatter_direction.is_near_zero() {
-            // 0-scatter direction leads to NaNs and unwanted infinities
-            scatter_direction = Point::new(hit.normal.x(), hit.normal.y(), hit.normal.z());
+    fn eval(&self, _: &Vec3, wi: &Vec3, hit: &HitRecord) -> Color {
+        let cos_theta = dot(&wi.unit(), &hit.normal);
+        if cos_theta <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        &self.albedo * (cos_theta / std::f64::consts::PI)
+    }
+
+    fn pdf(&self, _: &Vec3, wi: &Vec3, hit: &HitRecord) -> f64 {
+        CosinePdf::new(&hit.normal).value(wi)
+    }
+
+    /// Samples directions in proportion to the cosine term, so `f / pdf` is exactly the albedo.
+    fn sample(&self, wo: &Vec3, hit: &HitRecord, u: [f64; 3]) -> Option<BsdfSample> {
+        let cosine_pdf = CosinePdf::new(&hit.normal);
+        let wi = cosine_pdf.generate([u[1], u[2]]);
+        let pdf = cosine_pdf.value(&wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            f: self.eval(wo, &wi, hit),
+            wi,
+            pdf,
+            is_delta: false,
+        })
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("lambertian").vec3(&self.albedo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::point::Point;
+
+    /// Integrates `f` over the whole sphere of directions with a midpoint rule in spherical
+    /// coordinates.
+    fn integrate_sphere(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (n_theta, n_phi) = (400, 200);
+        let d_theta = PI / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let w = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += f(&w) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        sum
+    }
+
+    #[test]
+    fn test_eval_pdf_and_sample_agree() {
+        let lambertian = Lambertian::new(Color::new(0.8, 0.5, 0.2));
+        let hit = HitRecord::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.6, 0.0, 0.8),
+            1.0,
+            true,
+            &lambertian,
+        );
+        let wo = Vec3::new(0.0, 0.0, 1.0);
+
+        let albedo = integrate_sphere(|wi| lambertian.eval(&wo, wi, &hit).x());
+        let total_pdf = integrate_sphere(|wi| lambertian.pdf(&wo, wi, &hit));
+        assert!((albedo - 0.8).abs() < 1e-3);
+        assert!((total_pdf - 1.0).abs() < 1e-3);
+
+        for i in 0..100 {
+            let u = [0.5, (i as f64 + 0.5) / 100.0, (i as f64 * 0.618).fract()];
+            let sample = lambertian.sample(&wo, &hit, u).unwrap();
+            let pdf = lambertian.pdf(&wo, &sample.wi, &hit);
+            let f = lambertian.eval(&wo, &sample.wi, &hit);
+
+            assert!((sample.pdf - pdf).abs() < 1e-12);
+            assert!((&sample.f - &f).len() < 1e-12);
+            assert!((sample.weight() - Color::new(0.8, 0.5, 0.2)).len() < 1e-9);
         }
-        let scatter_origin = Point::new(hit.point.x(), hit.point.y(), hit.point.z());
-        let scattered_ray = Ray::new(scatter_origin, scatter_direction);
-        let color = Color::new(self.albedo.x(), self.albedo.y(), self.albedo.z());
-        (scattered_ray, color)
     }
 }
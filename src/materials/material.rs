@@ -1,5 +1,78 @@
-use crate::{color::Color, hit_record::HitRecord, ray::Ray};
+use crate::{
+    color::Color, description::Description, hit_record::HitRecord, ray::Ray,
+    samplers::sampler::Sampler, vec3::Vec3,
+};
 
+/// Describes how light scatters at a surface through its BSDF.
+///
+/// All directions are in world space and point away from the hit point: `wo` back towards where
+/// the light is going (the viewer), and `wi` towards where it comes from.
 pub trait Material {
-    fn scatter(&self, incident: &Ray, hit: &HitRecord) -> (Ray, Color);
+    /// The value of the BSDF for the pair of directions, multiplied by the cosine foreshortening
+    /// term `|cos(theta_i)|`. Delta lobes are never included, as they can't be evaluated for an
+    /// arbitrary pair of directions.
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &HitRecord) -> Color;
+
+    /// The density, per unit solid angle, with which `sample` returns `wi`. Like `eval`, this only
+    /// accounts for non-delta lobes.
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &HitRecord) -> f64;
+
+    /// Samples an incident direction. `u` holds three uniform random numbers in `[0, 1)`: the
+    /// first chooses between lobes and the others sample a direction within the chosen one.
+    /// Returns None when the path is absorbed.
+    fn sample(&self, wo: &Vec3, hit: &HitRecord, u: [f64; 3]) -> Option<BsdfSample>;
+
+    /// The radiance the surface emits. Most materials don't emit any light.
+    fn emitted(&self) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Samples a scattered ray and the attenuation it carries, with the next dimensions of
+    /// `sampler`.
+    fn scatter(&self, incident: &Ray, hit: &HitRecord, sampler: &mut dyn Sampler) -> (Ray, Color) {
+        let wo = -incident.direction.unit();
+        let scatter_origin = hit.point.clone();
+        match self.sample(&wo, hit, bsdf_sample_u(sampler)) {
+            Some(sample) => {
+                let attenuation = sample.weight();
+                (incident.spawn(scatter_origin, sample.wi), attenuation)
+            }
+            // the path was absorbed
+            None => (
+                incident.spawn(scatter_origin, hit.normal.clone()),
+                Color::new(0.0, 0.0, 0.0),
+            ),
+        }
+    }
+
+    /// Adds the kind of material and its parameters to `description`.
+    fn describe(&self, description: &mut Description);
+}
+
+/// A direction sampled from a BSDF.
+pub struct BsdfSample {
+    pub wi: Vec3,
+    /// The BSDF value multiplied by the cosine term, as returned by `Material::eval`.
+    pub f: Color,
+    /// The density `wi` was sampled with. For delta lobes this is the discrete probability of
+    /// having chosen the lobe, and `f` holds the lobe's integrated contribution.
+    pub pdf: f64,
+    /// True if `wi` was sampled from a delta (perfectly specular) lobe, which `eval` and `pdf`
+    /// can't reproduce.
+    pub is_delta: bool,
+}
+
+impl BsdfSample {
+    /// The sample's contribution to a Monte Carlo estimate, `f / pdf`.
+    pub fn weight(&self) -> Color {
+        &self.f / self.pdf
+    }
+}
+
+/// Draws the three numbers `Material::sample` takes from the next dimensions of `sampler`: one
+/// to choose the lobe, then a pair for the direction, so that the direction is stratified in 2D.
+pub fn bsdf_sample_u(sampler: &mut dyn Sampler) -> [f64; 3] {
+    let lobe = sampler.get_1d();
+    let [u, v] = sampler.get_2d();
+    [lobe, u, v]
 }
@@ -1,32 +1,271 @@
 use crate::{
-    color::Color, hit_record::HitRecord, interval::Interval, point::Point, ray::Ray, vec3::Vec3,
+    color::Color,
+    description::Description,
+    hit_record::HitRecord,
+    interval::Interval,
+    onb::Onb,
+    vec3::{dot, Vec3},
 };
 
-use super::material::Material;
+use super::{
+    material::{BsdfSample, Material},
+    microfacet::{fresnel_schlick, reflect, TrowbridgeReitz},
+};
 
 pub struct Metal {
     albedo: Color,
     fuzz: f64,
+    /// Whether `fuzz` is the roughness of a microfacet surface rather than the radius of a
+    /// random perturbation of the mirror direction.
+    microfacet: bool,
 }
 
 impl Metal {
+    /// A mirror tinted by `albedo`, whose reflections are blurred by adding a random point within
+    /// a sphere of radius `fuzz` to the mirror direction. A fuzz of 0 gives a perfect mirror.
+    /// Reflections blurred below the surface are absorbed.
+    ///
+    /// The blurred lobe has no density that can be evaluated for a given direction, so it's
+    /// sampled like a delta lobe: paths leaving it don't sample lights directly. Use
+    /// `Metal::microfacet` for glossy metal that works with light sampling.
     pub fn new(albedo: Color, fuzz: f64) -> Self {
         let fuzz_norm = Interval::new(0.0, 1.0).clamp(fuzz);
         Metal {
             albedo,
             fuzz: fuzz_norm,
+            microfacet: false,
+        }
+    }
+
+    /// A rough metal with a GGX microfacet BRDF. `f0` is the reflectance at normal incidence (the
+    /// Schlick F0 tint), rising towards white at grazing angles. `roughness` is used directly as
+    /// the GGX alpha, and a roughness of 0 gives a perfect mirror.
+    pub fn microfacet(f0: Color, roughness: f64) -> Self {
+        let roughness_norm = Interval::new(0.0, 1.0).clamp(roughness);
+        Metal {
+            albedo: f0,
+            fuzz: roughness_norm,
+            microfacet: true,
+        }
+    }
+
+    fn distribution(&self) -> TrowbridgeReitz {
+        TrowbridgeReitz::new(self.fuzz, self.fuzz)
+    }
+
+    fn sample_fuzzy(&self, wo: &Vec3, hit: &HitRecord, u: [f64; 2]) -> Option<BsdfSample> {
+        let wi = reflect(wo, &hit.normal) + Vec3::on_unit_sphere(u) * self.fuzz;
+        if dot(&wi, &hit.normal) <= 0.0 {
+            return None;
         }
+        Some(BsdfSample {
+            wi: wi.unit(),
+            f: self.albedo.clone(),
+            pdf: 1.0,
+            is_delta: true,
+        })
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, incident: &Ray, hit: &HitRecord) -> (Ray, Color) {
-        let reflection_direction = incident.direction.reflect(&hit.normal);
-        let reflection_direction_fuzzy =
-            reflection_direction.unit() + (Vec3::random_unit() * self.fuzz);
-        let reflection_origin = Point::new(hit.point.x(), hit.point.y(), hit.point.z());
-        let reflected_ray = Ray::new(reflection_origin, reflection_direction_fuzzy);
-        let color = Color::new(self.albedo.x(), self.albedo.y(), self.albedo.z());
-        (reflected_ray, color)
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &HitRecord) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let distribution = self.distribution();
+        if !self.microfacet || distribution.is_smooth() {
+            return black;
+        }
+
+        let onb = Onb::new(&hit.normal);
+        let wo = onb.to_local(wo);
+        let wi = onb.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return black;
+        }
+        let wh = (&wo + &wi).unit();
+        let fresnel = fresnel_schlick(&self.albedo, dot(&wi, &wh));
+        // the cosine term cancels with the cos(theta_i) in the denominator
+        fresnel * (distribution.d(&wh) * distribution.g(&wo, &wi) / (4.0 * wo.z()))
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &HitRecord) -> f64 {
+        let distribution = self.distribution();
+        if !self.microfacet || distribution.is_smooth() {
+            return 0.0;
+        }
+
+        let onb = Onb::new(&hit.normal);
+        let wo = onb.to_local(wo);
+        let wi = onb.to_local(wi);
+        if wo.z() <= 0.0 || wi.z() <= 0.0 {
+            return 0.0;
+        }
+        let wh = (&wo + &wi).unit();
+        distribution.pdf(&wo, &wh) / (4.0 * dot(&wo, &wh))
+    }
+
+    fn sample(&self, wo: &Vec3, hit: &HitRecord, u: [f64; 3]) -> Option<BsdfSample> {
+        if !self.microfacet {
+            return self.sample_fuzzy(wo, hit, [u[1], u[2]]);
+        }
+        let distribution = self.distribution();
+        if distribution.is_smooth() {
+            let cos_theta = dot(wo, &hit.normal);
+            return Some(BsdfSample {
+                wi: reflect(wo, &hit.normal),
+                f: fresnel_schlick(&self.albedo, cos_theta),
+                pdf: 1.0,
+                is_delta: true,
+            });
+        }
+
+        let onb = Onb::new(&hit.normal);
+        let wo_local = onb.to_local(wo);
+        let wm = distribution.sample_wm(&wo_local, [u[1], u[2]]);
+        let wi_local = reflect(&wo_local, &wm);
+        if wi_local.z() <= 0.0 {
+            return None;
+        }
+
+        let wi = onb.to_world(&wi_local);
+        let pdf = self.pdf(wo, &wi, hit);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            f: self.eval(wo, &wi, hit),
+            wi,
+            pdf,
+            is_delta: false,
+        })
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("metal")
+            .vec3(&self.albedo)
+            .f64(self.fuzz)
+            .u64(self.microfacet as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::{point::Point, samplers::low_discrepancy::radical_inverse, vec3::cross};
+
+    /// Integrates `f` over the whole sphere of directions with a midpoint rule in spherical
+    /// coordinates.
+    fn integrate_sphere(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (n_theta, n_phi) = (600, 300);
+        let d_theta = PI / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let w = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += f(&w) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        sum
+    }
+
+    #[test]
+    fn test_eval_pdf_and_sample_agree() {
+        let metal = Metal::microfacet(Color::new(0.9, 0.6, 0.3), 0.4);
+        let hit = HitRecord::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            true,
+            &metal,
+        );
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+
+        let n = 100_000;
+        let mut succeeded = 0;
+        let mut estimate = 0.0;
+        for i in 0..n {
+            let u = [0.5, radical_inverse(2, i), radical_inverse(3, i)];
+            let Some(sample) = metal.sample(&wo, &hit, u) else {
+                continue;
+            };
+            let pdf = metal.pdf(&wo, &sample.wi, &hit);
+            let f = metal.eval(&wo, &sample.wi, &hit);
+            assert!((sample.pdf - pdf).abs() <= 1e-9 * pdf);
+            assert!((&sample.f - &f).len() <= 1e-9 * f.len());
+            succeeded += 1;
+            estimate += sample.weight().x();
+        }
+        estimate /= n as f64;
+
+        // reflections below the horizon are discarded, so the pdf integrates to the fraction of
+        // samples that survive, and `f / pdf` estimates the albedo that `eval` integrates to
+        let total_pdf = integrate_sphere(|wi| metal.pdf(&wo, wi, &hit));
+        let albedo = integrate_sphere(|wi| metal.eval(&wo, wi, &hit).x());
+        assert!((total_pdf - succeeded as f64 / n as f64).abs() < 5e-3);
+        assert!((albedo - estimate).abs() < 5e-3);
+    }
+
+    #[test]
+    fn test_smooth_is_a_fresnel_weighted_mirror() {
+        let metal = Metal::microfacet(Color::new(0.9, 0.6, 0.3), 0.0);
+        let hit = HitRecord::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            true,
+            &metal,
+        );
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let sample = metal.sample(&wo, &hit, [0.5, 0.5, 0.5]).unwrap();
+
+        assert!(sample.is_delta);
+        assert!((sample.wi - Vec3::new(-0.6, 0.0, 0.8)).len() < 1e-12);
+        assert!((sample.f - fresnel_schlick(&Color::new(0.9, 0.6, 0.3), 0.8)).len() < 1e-12);
+        assert_eq!(0.0, metal.pdf(&wo, &Vec3::new(-0.6, 0.0, 0.8), &hit));
+        assert!(metal
+            .eval(&wo, &Vec3::new(-0.6, 0.0, 0.8), &hit)
+            .is_near_zero());
+    }
+
+    #[test]
+    fn test_fuzzy_reflects_about_the_mirror_direction_with_a_flat_tint() {
+        let albedo = Color::new(0.8, 0.6, 0.2);
+        let mirror = Metal::new(albedo.clone(), 0.0);
+        let fuzzy = Metal::new(albedo.clone(), 0.3);
+        let hit = HitRecord::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            true,
+            &mirror,
+        );
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let mirrored = Vec3::new(-0.6, 0.0, 0.8);
+
+        let sample = mirror.sample(&wo, &hit, [0.5, 0.25, 0.75]).unwrap();
+        assert!((&sample.wi - &mirrored).len() < 1e-12);
+        for i in 0..1000 {
+            let u = [0.5, radical_inverse(2, i), radical_inverse(3, i)];
+            let Some(sample) = fuzzy.sample(&wo, &hit, u) else {
+                continue;
+            };
+            // the direction is within the fuzz sphere around the mirror direction, and the tint
+            // doesn't depend on it
+            assert!(sample.is_delta);
+            assert!(dot(&sample.wi, &hit.normal) > 0.0);
+            assert!(cross(sample.wi.clone(), mirrored.clone()).len() <= 0.3 + 1e-12);
+            assert_eq!(albedo.x(), sample.weight().x());
+            assert_eq!(albedo.z(), sample.weight().z());
+            assert_eq!(0.0, fuzzy.pdf(&wo, &sample.wi, &hit));
+        }
     }
 }
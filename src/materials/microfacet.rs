@@ -0,0 +1,300 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::Color,
+    vec3::{cross, dot, Vec3},
+};
+
+// Everything in this module works in a local shading space where the surface normal is +z. The
+// outgoing direction `wo` always points away from the surface, towards the viewer.
+
+/// Trowbridge-Reitz (GGX) distribution of microfacet normals, with separate roughness along the
+/// tangent (`alpha_x`) and bitangent (`alpha_y`) for anisotropic surfaces.
+pub struct TrowbridgeReitz {
+    alpha_x: f64,
+    alpha_y: f64,
+}
+
+impl TrowbridgeReitz {
+    pub fn new(alpha_x: f64, alpha_y: f64) -> Self {
+        // very small alphas are numerically unstable; such surfaces are treated as smooth anyway
+        TrowbridgeReitz {
+            alpha_x: alpha_x.max(1e-4),
+            alpha_y: alpha_y.max(1e-4),
+        }
+    }
+
+    /// Maps the artist-facing `roughness` and `anisotropic` parameters (both in `[0, 1]`) onto
+    /// the distribution's alphas, as described in Burley's "Physically Based Shading at Disney".
+    pub fn from_roughness(roughness: f64, anisotropic: f64) -> Self {
+        let aspect = (1.0 - 0.9 * anisotropic).sqrt();
+        let alpha = roughness * roughness;
+        TrowbridgeReitz::new(alpha / aspect, alpha * aspect)
+    }
+
+    /// Returns true if the distribution is so narrow that it should be treated as a perfect
+    /// mirror rather than evaluated as a microfacet lobe.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < 1e-3
+    }
+
+    /// The density of microfacets oriented along `wm`.
+    pub fn d(&self, wm: &Vec3) -> f64 {
+        if wm.z() <= 0.0 {
+            return 0.0;
+        }
+        let x = wm.x() / self.alpha_x;
+        let y = wm.y() / self.alpha_y;
+        let denominator = x * x + y * y + wm.z() * wm.z();
+        1.0 / (PI * self.alpha_x * self.alpha_y * denominator * denominator)
+    }
+
+    /// Smith's auxiliary function, measuring the microfacet area masked when viewed from `w`.
+    pub fn lambda(&self, w: &Vec3) -> f64 {
+        let cos_sq = w.z() * w.z();
+        if cos_sq == 0.0 {
+            return f64::INFINITY;
+        }
+        let x = w.x() * self.alpha_x;
+        let y = w.y() * self.alpha_y;
+        let alpha_tan_sq = (x * x + y * y) / cos_sq;
+        ((1.0 + alpha_tan_sq).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Height-correlated masking-shadowing term for the pair of directions.
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Density with which `sample_wm` returns `wm` when seen from `wo`.
+    pub fn pdf(&self, wo: &Vec3, wm: &Vec3) -> f64 {
+        if wo.z() == 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) / wo.z().abs() * self.d(wm) * dot(wo, wm).max(0.0)
+    }
+
+    /// Samples a microfacet normal from the distribution of normals visible from `wo`, following
+    /// Heitz's "Sampling the GGX Distribution of Visible Normals".
+    pub fn sample_wm(&self, wo: &Vec3, u: [f64; 2]) -> Vec3 {
+        // stretch the view direction into the configuration where the distribution is isotropic
+        // with unit roughness
+        let mut wh = Vec3::new(self.alpha_x * wo.x(), self.alpha_y * wo.y(), wo.z()).unit();
+        if wh.z() < 0.0 {
+            wh = -wh;
+        }
+
+        let t1 = if wh.z() < 0.99999 {
+            cross(Vec3::new(0.0, 0.0, 1.0), wh.clone()).unit()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = cross(wh.clone(), t1.clone());
+
+        // sample a point on the projected hemisphere, warping it towards the visible half
+        let r = u[0].sqrt();
+        let phi = 2.0 * PI * u[1];
+        let p1 = r * phi.cos();
+        let p2 = r * phi.sin();
+        let s = 0.5 * (1.0 + wh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * p2;
+        let pz = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+        let nh = t1 * p1 + t2 * p2 + wh * pz;
+
+        // unstretch back to the original distribution
+        Vec3::new(
+            self.alpha_x * nh.x(),
+            self.alpha_y * nh.y(),
+            nh.z().max(1e-6),
+        )
+        .unit()
+    }
+}
+
+/// Generalised Trowbridge-Reitz distribution with `gamma = 1`, used by the clearcoat lobe for its
+/// long tail.
+pub struct Gtr1 {
+    alpha: f64,
+}
+
+impl Gtr1 {
+    pub fn new(alpha: f64) -> Self {
+        Gtr1 {
+            alpha: alpha.max(1e-4),
+        }
+    }
+
+    pub fn d(&self, wm: &Vec3) -> f64 {
+        if wm.z() <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let cos_sq = wm.z() * wm.z();
+        (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * cos_sq))
+    }
+
+    /// Samples a microfacet normal proportionally to `D(wm) * cos(theta_m)`.
+    pub fn sample_wm(&self, u: [f64; 2]) -> Vec3 {
+        let a2 = self.alpha * self.alpha;
+        let cos_theta = ((1.0 - a2.powf(1.0 - u[0])) / (1.0 - a2)).max(0.0).sqrt();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u[1];
+        Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+    }
+
+    /// Density of `sample_wm` returning `wm`.
+    pub fn pdf(&self, wm: &Vec3) -> f64 {
+        self.d(wm) * wm.z()
+    }
+}
+
+/// Unpolarised Fresnel reflectance of a dielectric interface, where `eta` is the ratio of the
+/// index of refraction below the surface to the one above it.
+pub fn fresnel_dielectric(cos_theta_i: f64, eta: f64) -> f64 {
+    let mut cos_theta_i = cos_theta_i.clamp(-1.0, 1.0);
+    let mut eta = eta;
+    if cos_theta_i < 0.0 {
+        // the ray arrives from below, so the interface is seen the other way around
+        eta = 1.0 / eta;
+        cos_theta_i = -cos_theta_i;
+    }
+
+    let sin_sq_theta_i = 1.0 - cos_theta_i * cos_theta_i;
+    let sin_sq_theta_t = sin_sq_theta_i / (eta * eta);
+    if sin_sq_theta_t >= 1.0 {
+        return 1.0; // total internal reflection
+    }
+    let cos_theta_t = (1.0 - sin_sq_theta_t).max(0.0).sqrt();
+
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
+
+/// `(1 - cos)^5`, the interpolation weight in Schlick's Fresnel approximation.
+pub fn schlick_weight(cos_theta: f64) -> f64 {
+    let m = (1.0 - cos_theta).clamp(0.0, 1.0);
+    let m_sq = m * m;
+    m_sq * m_sq * m
+}
+
+/// Schlick's approximation of the Fresnel reflectance for a surface with normal-incidence
+/// reflectance `f0`.
+pub fn fresnel_schlick(f0: &Color, cos_theta: f64) -> Color {
+    let w = schlick_weight(cos_theta);
+    f0 * (1.0 - w) + Color::new(w, w, w)
+}
+
+/// Mirrors `wo` about the normal `n`, giving a direction on the same side of the surface.
+pub fn reflect(wo: &Vec3, n: &Vec3) -> Vec3 {
+    -wo.reflect(n)
+}
+
+/// Refracts `wo` through the interface with normal `n`. `eta` is the relative index of refraction
+/// of the side `n` doesn't point into. Returns the refracted direction and the relative index
+/// actually crossed, or None on total internal reflection.
+pub fn refract(wo: &Vec3, n: &Vec3, eta: f64) -> Option<(Vec3, f64)> {
+    let mut cos_theta_i = dot(n, wo);
+    let mut eta = eta;
+    let mut n = n.clone();
+    if cos_theta_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_theta_i = -cos_theta_i;
+        n = -n;
+    }
+
+    let sin_sq_theta_i = (1.0 - cos_theta_i * cos_theta_i).max(0.0);
+    let sin_sq_theta_t = sin_sq_theta_i / (eta * eta);
+    if sin_sq_theta_t >= 1.0 {
+        return None;
+    }
+    let cos_theta_t = (1.0 - sin_sq_theta_t).max(0.0).sqrt();
+
+    let wi = -(wo / eta) + n * (cos_theta_i / eta - cos_theta_t);
+    Some((wi, eta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Integrates `f` over the hemisphere around +z with a midpoint rule in spherical coordinates.
+    fn integrate_hemisphere(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (n_theta, n_phi) = (400, 400);
+        let d_theta = PI / 2.0 / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let w = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += f(&w) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        sum
+    }
+
+    #[test]
+    fn test_trowbridge_reitz_normalised() {
+        let distribution = TrowbridgeReitz::new(0.5, 0.2);
+        let projected_area = integrate_hemisphere(|wm| distribution.d(wm) * wm.z());
+
+        assert!((projected_area - 1.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_trowbridge_reitz_visible_pdf_normalised() {
+        let distribution = TrowbridgeReitz::new(0.3, 0.6);
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let total = integrate_hemisphere(|wm| distribution.pdf(&wo, wm));
+
+        assert!((total - 1.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_gtr1_pdf_normalised() {
+        let distribution = Gtr1::new(0.1);
+        let total = integrate_hemisphere(|wm| distribution.pdf(wm));
+
+        assert!((total - 1.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_fresnel_dielectric_normal_incidence() {
+        let eta = 1.5;
+        let exp = ((eta - 1.0) / (eta + 1.0)) * ((eta - 1.0) / (eta + 1.0));
+        let res = fresnel_dielectric(1.0, eta);
+
+        assert!((exp - res).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fresnel_dielectric_total_internal_reflection() {
+        let res = fresnel_dielectric(-0.2, 1.5);
+
+        assert_eq!(1.0, res);
+    }
+
+    #[test]
+    fn test_refract_snell() {
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let eta = 1.5;
+        let (wi, eta_crossed) = refract(&wo, &n, eta).unwrap();
+
+        assert_eq!(eta, eta_crossed);
+        assert!((wo.x() - eta * -wi.x()).abs() < 1e-12);
+        assert!(wi.z() < 0.0);
+        assert!((wi.len() - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,535 @@
+use std::f64::consts::PI;
+
+use crate::{
+    color::{luminance, Color},
+    description::Description,
+    hit_record::HitRecord,
+    interval::Interval,
+    onb::Onb,
+    vec3::{dot, Vec3},
+};
+
+use super::{
+    material::{BsdfSample, Material},
+    microfacet::{
+        fresnel_dielectric, fresnel_schlick, reflect, refract, schlick_weight, Gtr1,
+        TrowbridgeReitz,
+    },
+};
+
+/**
+A single "uber" material driven by the parameters of Burley's principled BSDF, as used at Disney
+and in most DCC tools and interchange formats (glTF, MTL).
+
+It is made up of four lobes, each weighted by the parameters:
+- a diffuse lobe with Burley's retro-reflection and an additive sheen term
+- a specular reflection lobe using an anisotropic GGX distribution
+- a rough dielectric lobe that reflects and refracts according to `ior`
+- a clearcoat lobe using the long-tailed GTR1 distribution
+
+All scalar parameters are expected in `[0, 1]`, except `ior`. Construct with `Principled::new` and
+override the fields that matter:
+```
+# use ray_tracing::{color::Color, materials::principled::Principled};
+let gold = Principled {
+    metallic: 1.0,
+    roughness: 0.2,
+    ..Principled::new(Color::new(1.0, 0.78, 0.34))
+};
+```
+*/
+pub struct Principled {
+    pub base_color: Color,
+    pub metallic: f64,
+    pub roughness: f64,
+    pub specular: f64,
+    pub specular_tint: f64,
+    pub sheen: f64,
+    pub clearcoat: f64,
+    pub clearcoat_gloss: f64,
+    pub transmission: f64,
+    pub ior: f64,
+    pub anisotropic: f64,
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Principled {
+            base_color,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            specular_tint: 0.0,
+            sheen: 0.0,
+            clearcoat: 0.0,
+            clearcoat_gloss: 1.0,
+            transmission: 0.0,
+            ior: 1.5,
+            anisotropic: 0.0,
+        }
+    }
+
+    /// Resolves the parameters into lobe weights and distributions for a hit on the front or back
+    /// face of the surface.
+    fn lobes(&self, front_face: bool) -> Lobes {
+        let unit = Interval::new(0.0, 1.0);
+        let metallic = unit.clamp(self.metallic);
+        let transmission = unit.clamp(self.transmission);
+        let specular_tint = unit.clamp(self.specular_tint);
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission);
+        let transmission_weight = (1.0 - metallic) * transmission;
+        let specular_weight = 1.0 - transmission_weight;
+        let clearcoat_weight = 0.25 * unit.clamp(self.clearcoat);
+
+        // the tint is the base color's hue and saturation, with its luminance normalised out
+        let base_luminance = luminance(&self.base_color);
+        let tint = if base_luminance > 0.0 {
+            &self.base_color / base_luminance
+        } else {
+            Color::new(1.0, 1.0, 1.0)
+        };
+        let dielectric_f0 = mix(&Color::new(1.0, 1.0, 1.0), &tint, specular_tint)
+            * (0.08 * unit.clamp(self.specular));
+        let specular_f0 = mix(&dielectric_f0, &self.base_color, metallic);
+
+        // sample each lobe in proportion to its weight
+        let total = diffuse_weight + specular_weight + clearcoat_weight + transmission_weight;
+        let probabilities = [
+            diffuse_weight / total,
+            specular_weight / total,
+            clearcoat_weight / total,
+            transmission_weight / total,
+        ];
+
+        let roughness = unit.clamp(self.roughness);
+        let clearcoat_alpha = 0.1 * (1.0 - self.clearcoat_gloss) + 0.001 * self.clearcoat_gloss;
+        Lobes {
+            diffuse_weight,
+            specular_weight,
+            clearcoat_weight,
+            transmission_weight,
+            probabilities,
+            roughness,
+            specular_f0,
+            specular: TrowbridgeReitz::from_roughness(roughness, unit.clamp(self.anisotropic)),
+            clearcoat: Gtr1::new(clearcoat_alpha),
+            clearcoat_masking: TrowbridgeReitz::new(0.25, 0.25),
+            eta: if front_face { self.ior } else { 1.0 / self.ior },
+        }
+    }
+
+    /// Evaluates the non-delta lobes of the BSDF, multiplied by the cosine term, for directions in
+    /// the local shading frame.
+    fn eval_local(&self, lobes: &Lobes, wo: &Vec3, wi: &Vec3) -> Color {
+        let cos_o = wo.z();
+        let cos_i = wi.z();
+        let mut f = Color::new(0.0, 0.0, 0.0);
+        if cos_o <= 0.0 || cos_i == 0.0 {
+            return f;
+        }
+
+        if cos_i > 0.0 {
+            let wh = (wo + wi).unit();
+            let cos_d = dot(wi, &wh);
+
+            if lobes.diffuse_weight > 0.0 {
+                let fl = schlick_weight(cos_i);
+                let fv = schlick_weight(cos_o);
+                let rr = 2.0 * lobes.roughness * cos_d * cos_d;
+                let lambert = (1.0 - 0.5 * fl) * (1.0 - 0.5 * fv);
+                let retro_reflection = rr * (fl + fv + fl * fv * (rr - 1.0));
+                f += &self.base_color * ((lambert + retro_reflection) / PI * lobes.diffuse_weight);
+
+                let sheen = self.sheen * schlick_weight(cos_d) * lobes.diffuse_weight;
+                f += Color::new(sheen, sheen, sheen);
+            }
+
+            if lobes.specular_weight > 0.0 && !lobes.specular.is_smooth() {
+                let fresnel = fresnel_schlick(&lobes.specular_f0, cos_d);
+                let d = lobes.specular.d(&wh);
+                let g = lobes.specular.g(wo, wi);
+                f += fresnel * (d * g / (4.0 * cos_o * cos_i) * lobes.specular_weight);
+            }
+
+            if lobes.clearcoat_weight > 0.0 {
+                let fresnel = 0.04 + 0.96 * schlick_weight(cos_d);
+                let d = lobes.clearcoat.d(&wh);
+                let g = lobes.clearcoat_masking.g(wo, wi);
+                let clearcoat = lobes.clearcoat_weight * d * fresnel * g / (4.0 * cos_o * cos_i);
+                f += Color::new(clearcoat, clearcoat, clearcoat);
+            }
+        }
+
+        if lobes.transmission_weight > 0.0 && !lobes.specular.is_smooth() {
+            f += self.eval_dielectric(lobes, wo, wi) * lobes.transmission_weight;
+        }
+
+        f * cos_i.abs()
+    }
+
+    /// Evaluates the rough dielectric lobe, without the cosine term, following Walter et al.'s
+    /// "Microfacet Models for Refraction through Rough Surfaces".
+    fn eval_dielectric(&self, lobes: &Lobes, wo: &Vec3, wi: &Vec3) -> Color {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let Some((wm, eta_crossed)) = generalised_half_vector(lobes.eta, wo, wi) else {
+            return black;
+        };
+        let cos_o = wo.z();
+        let cos_i = wi.z();
+        let fresnel = fresnel_dielectric(dot(wo, &wm), lobes.eta);
+        let d = lobes.specular.d(&wm);
+        let g = lobes.specular.g(wo, wi);
+
+        if cos_i > 0.0 {
+            let reflected = d * g * fresnel / (4.0 * cos_i * cos_o);
+            return Color::new(reflected, reflected, reflected);
+        }
+
+        let denominator = (dot(wi, &wm) + dot(wo, &wm) / eta_crossed).powi(2) * cos_i * cos_o;
+        let transmitted =
+            d * (1.0 - fresnel) * g * (dot(wi, &wm) * dot(wo, &wm) / denominator).abs();
+        // radiance is compressed into a smaller solid angle when it enters a denser medium
+        &self.base_color * (transmitted / (eta_crossed * eta_crossed))
+    }
+
+    /// The density with which `sample_local` produces `wi`, over the non-delta lobes.
+    fn pdf_local(&self, lobes: &Lobes, wo: &Vec3, wi: &Vec3) -> f64 {
+        let cos_o = wo.z();
+        let cos_i = wi.z();
+        if cos_o <= 0.0 || cos_i == 0.0 {
+            return 0.0;
+        }
+        let [p_diffuse, p_specular, p_clearcoat, p_transmission] = lobes.probabilities;
+        let mut pdf = 0.0;
+
+        if cos_i > 0.0 {
+            let wh = (wo + wi).unit();
+            let cos_oh = dot(wo, &wh);
+            pdf += p_diffuse * cos_i / PI;
+            if !lobes.specular.is_smooth() {
+                pdf += p_specular * lobes.specular.pdf(wo, &wh) / (4.0 * cos_oh);
+            }
+            pdf += p_clearcoat * lobes.clearcoat.pdf(&wh) / (4.0 * cos_oh);
+        }
+
+        if p_transmission > 0.0 && !lobes.specular.is_smooth() {
+            if let Some((wm, eta_crossed)) = generalised_half_vector(lobes.eta, wo, wi) {
+                let reflectance = fresnel_dielectric(dot(wo, &wm), lobes.eta);
+                let microfacet_pdf = lobes.specular.pdf(wo, &wm);
+                pdf += p_transmission
+                    * if cos_i > 0.0 {
+                        microfacet_pdf / (4.0 * dot(wo, &wm).abs()) * reflectance
+                    } else {
+                        let denominator = (dot(wi, &wm) + dot(wo, &wm) / eta_crossed).powi(2);
+                        let dwm_dwi = dot(wi, &wm).abs() / denominator;
+                        microfacet_pdf * dwm_dwi * (1.0 - reflectance)
+                    };
+            }
+        }
+
+        pdf
+    }
+
+    /// Picks a lobe with `u[0]` and samples an incident direction from it with the rest of `u`.
+    fn sample_local(&self, lobes: &Lobes, wo: &Vec3, u: [f64; 3]) -> Option<BsdfSample> {
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        // choose a lobe, then rescale `uc` so it can be reused within the lobe
+        let mut uc = u[0];
+        let u = [u[1], u[2]];
+        let mut lobe = lobes.probabilities.len() - 1;
+        for (i, p) in lobes.probabilities.iter().enumerate() {
+            if uc < *p {
+                lobe = i;
+                uc /= p;
+                break;
+            }
+            uc -= p;
+        }
+        let [_, p_specular, _, p_transmission] = lobes.probabilities;
+
+        let wi = match lobe {
+            0 => Vec3::random_cosine_direction(u),
+            1 if lobes.specular.is_smooth() => {
+                let fresnel = fresnel_schlick(&lobes.specular_f0, wo.z());
+                return Some(BsdfSample {
+                    wi: Vec3::new(-wo.x(), -wo.y(), wo.z()),
+                    f: fresnel * lobes.specular_weight,
+                    pdf: p_specular,
+                    is_delta: true,
+                });
+            }
+            1 => reflect(wo, &lobes.specular.sample_wm(wo, u)),
+            2 => reflect(wo, &lobes.clearcoat.sample_wm(u)),
+            _ if lobes.specular.is_smooth() => {
+                let reflectance = fresnel_dielectric(wo.z(), lobes.eta);
+                if uc < reflectance {
+                    let f = lobes.transmission_weight * reflectance;
+                    return Some(BsdfSample {
+                        wi: Vec3::new(-wo.x(), -wo.y(), wo.z()),
+                        f: Color::new(f, f, f),
+                        pdf: p_transmission * reflectance,
+                        is_delta: true,
+                    });
+                }
+                let (wi, eta_crossed) = refract(wo, &Vec3::new(0.0, 0.0, 1.0), lobes.eta)?;
+                let transmittance = 1.0 - reflectance;
+                return Some(BsdfSample {
+                    wi,
+                    f: &self.base_color
+                        * (lobes.transmission_weight * transmittance / (eta_crossed * eta_crossed)),
+                    pdf: p_transmission * transmittance,
+                    is_delta: true,
+                });
+            }
+            _ => {
+                let wm = lobes.specular.sample_wm(wo, u);
+                let reflectance = fresnel_dielectric(dot(wo, &wm), lobes.eta);
+                if uc < reflectance {
+                    reflect(wo, &wm)
+                } else {
+                    let (wi, _) = refract(wo, &wm, lobes.eta)?;
+                    if wi.z() >= 0.0 {
+                        return None;
+                    }
+                    wi
+                }
+            }
+        };
+
+        let pdf = self.pdf_local(lobes, wo, &wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            f: self.eval_local(lobes, wo, &wi),
+            wi,
+            pdf,
+            is_delta: false,
+        })
+    }
+}
+
+impl Material for Principled {
+    fn eval(&self, wo: &Vec3, wi: &Vec3, hit: &HitRecord) -> Color {
+        let onb = Onb::new(&hit.normal);
+        let lobes = self.lobes(hit.front_face);
+        self.eval_local(&lobes, &onb.to_local(wo), &onb.to_local(wi))
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3, hit: &HitRecord) -> f64 {
+        let onb = Onb::new(&hit.normal);
+        let lobes = self.lobes(hit.front_face);
+        self.pdf_local(&lobes, &onb.to_local(wo), &onb.to_local(wi))
+    }
+
+    fn sample(&self, wo: &Vec3, hit: &HitRecord, u: [f64; 3]) -> Option<BsdfSample> {
+        let onb = Onb::new(&hit.normal);
+        let lobes = self.lobes(hit.front_face);
+        let mut sample = self.sample_local(&lobes, &onb.to_local(wo), u)?;
+        sample.wi = onb.to_world(&sample.wi);
+        Some(sample)
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("principled")
+            .vec3(&self.base_color)
+            .f64s(&[
+                self.metallic,
+                self.roughness,
+                self.specular,
+                self.specular_tint,
+                self.sheen,
+                self.clearcoat,
+                self.clearcoat_gloss,
+                self.transmission,
+                self.ior,
+                self.anisotropic,
+            ]);
+    }
+}
+
+/// A `Principled` material's parameters resolved for a single hit.
+struct Lobes {
+    diffuse_weight: f64,
+    specular_weight: f64,
+    clearcoat_weight: f64,
+    transmission_weight: f64,
+    /// The probability of sampling each of the diffuse, specular, clearcoat and transmission lobes.
+    probabilities: [f64; 4],
+    roughness: f64,
+    specular_f0: Color,
+    specular: TrowbridgeReitz,
+    clearcoat: Gtr1,
+    clearcoat_masking: TrowbridgeReitz,
+    /// The relative index of refraction of crossing the surface from the side being shaded.
+    eta: f64,
+}
+
+/// Returns the microfacet normal that scatters `wo` into `wi` by reflection or refraction,
+/// oriented into the upper hemisphere, and the relative index of refraction crossed. Returns None
+/// for configurations that no microfacet can produce.
+fn generalised_half_vector(eta: f64, wo: &Vec3, wi: &Vec3) -> Option<(Vec3, f64)> {
+    let eta_crossed = if wi.z() > 0.0 { 1.0 } else { eta };
+    let wm = wi * eta_crossed + wo.clone();
+    if wi.z() == 0.0 || wm.len_sq() == 0.0 {
+        return None;
+    }
+    let mut wm = wm.unit();
+    if wm.z() < 0.0 {
+        wm = -wm;
+    }
+    // discard back-facing microfacets
+    if dot(&wm, wi) * wi.z() < 0.0 || dot(&wm, wo) * wo.z() < 0.0 {
+        return None;
+    }
+    Some((wm, eta_crossed))
+}
+
+fn mix(a: &Color, b: &Color, t: f64) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point::Point, samplers::low_discrepancy::radical_inverse};
+
+    /// Integrates `f` over the whole sphere of directions with a midpoint rule in spherical
+    /// coordinates.
+    fn integrate_sphere(f: impl Fn(&Vec3) -> f64) -> f64 {
+        let (n_theta, n_phi) = (600, 300);
+        let d_theta = PI / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let w = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += f(&w) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        sum
+    }
+
+    /// Averages `f` over the samples `material` takes towards `wo` from a surface facing +z, with
+    /// stratified numbers for the lobe and a Halton sequence for the direction. Samples that fail
+    /// count as zero.
+    fn sample_mean(
+        material: &Principled,
+        wo: &Vec3,
+        f: impl Fn(&BsdfSample) -> f64,
+    ) -> (f64, usize) {
+        let hit = surface(material);
+        let n = 200_000;
+        let mut sum = 0.0;
+        let mut succeeded = 0;
+        for i in 0..n {
+            let u = [
+                (i as f64 + 0.5) / n as f64,
+                radical_inverse(2, i as u64),
+                radical_inverse(3, i as u64),
+            ];
+            if let Some(sample) = material.sample(wo, &hit, u) {
+                sum += f(&sample);
+                succeeded += 1;
+            }
+        }
+        (sum / n as f64, succeeded)
+    }
+
+    fn surface(material: &Principled) -> HitRecord<'_> {
+        HitRecord::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            1.0,
+            true,
+            material,
+        )
+    }
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    #[test]
+    fn test_white_furnace() {
+        let diffuse = Principled {
+            specular: 0.0,
+            roughness: 0.0,
+            ..Principled::new(white())
+        };
+        let metal = Principled {
+            metallic: 1.0,
+            roughness: 0.3,
+            ..Principled::new(white())
+        };
+        for (material, lower) in [(&diffuse, 0.95), (&metal, 0.9)] {
+            let hit = surface(material);
+            for wo in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.6, 0.0, 0.8)] {
+                let albedo = integrate_sphere(|wi| material.eval(&wo, wi, &hit).y());
+
+                // a white surface under uniform light may lose energy, but never gain it
+                assert!(albedo <= 1.0 + 1e-2, "albedo {albedo}");
+                assert!(albedo >= lower, "albedo {albedo}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_pdf_integrates_to_sampled_fraction_and_matches_sample() {
+        let wo = Vec3::new(0.6, 0.0, 0.8);
+        let layered = Principled {
+            clearcoat: 1.0,
+            clearcoat_gloss: 0.5,
+            sheen: 0.5,
+            ..Principled::new(Color::new(0.8, 0.5, 0.3))
+        };
+        let metal = Principled {
+            metallic: 1.0,
+            roughness: 0.4,
+            anisotropic: 0.5,
+            ..Principled::new(white())
+        };
+        let glass = Principled {
+            transmission: 1.0,
+            roughness: 0.4,
+            ..Principled::new(white())
+        };
+        for material in [&layered, &metal, &glass] {
+            let hit = surface(material);
+
+            // samples that fail are directions the pdf doesn't cover, so the pdf integrates to
+            // the fraction that succeed rather than exactly 1
+            let total_pdf = integrate_sphere(|wi| material.pdf(&wo, wi, &hit));
+            let (_, succeeded) = sample_mean(material, &wo, |_| 0.0);
+            let succeeded = succeeded as f64 / 200_000.0;
+            assert!(total_pdf <= 1.0 + 1e-2, "pdf integrates to {total_pdf}");
+            assert!(
+                (total_pdf - succeeded).abs() < 5e-3,
+                "{total_pdf} vs {succeeded}"
+            );
+
+            // `f / pdf` is only an unbiased estimate of the albedo if `pdf` is the density
+            // `sample` actually draws from, and `f` is what `eval` returns
+            let albedo = integrate_sphere(|wi| material.eval(&wo, wi, &hit).y());
+            let (estimate, _) = sample_mean(material, &wo, |sample| {
+                let f = material.eval(&wo, &sample.wi, &hit).y();
+                let pdf = material.pdf(&wo, &sample.wi, &hit);
+                assert!((f - sample.f.y()).abs() <= 1e-9 * f.max(1.0));
+                assert!((pdf - sample.pdf).abs() <= 1e-9 * pdf.max(1.0));
+                sample.weight().y()
+            });
+            assert!((albedo - estimate).abs() < 5e-3, "{albedo} vs {estimate}");
+        }
+    }
+}
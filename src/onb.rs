@@ -0,0 +1,92 @@
+use crate::vec3::{cross, dot, Vec3};
+
+/// An orthonormal basis, for moving directions in and out of a local space where `w` (usually a
+/// surface normal) is the +z axis.
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    /// Builds a basis around the unit vector `w`, using the branchless construction from Duff et
+    /// al.'s "Building an Orthonormal Basis, Revisited".
+    pub fn new(w: &Vec3) -> Self {
+        let sign = 1.0_f64.copysign(w.z());
+        let a = -1.0 / (sign + w.z());
+        let b = w.x() * w.y() * a;
+        Onb {
+            u: Vec3::new(1.0 + sign * w.x() * w.x() * a, sign * b, -sign * w.x()),
+            v: Vec3::new(b, sign + w.y() * w.y() * a, -w.y()),
+            w: w.clone(),
+        }
+    }
+
+    /// Builds a basis around `w` whose `u` axis points along `tangent`, as far as it can while
+    /// staying perpendicular to `w`.
+    pub fn with_tangent(w: &Vec3, tangent: &Vec3) -> Self {
+        let w = w.unit();
+        let u = tangent - &(&w * dot(tangent, &w));
+        if u.is_near_zero() {
+            return Onb::new(&w);
+        }
+        let u = u.unit();
+        Onb {
+            v: cross(w.clone(), u.clone()),
+            u,
+            w,
+        }
+    }
+
+    /// Expresses the world space vector `v` in terms of the basis.
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(dot(v, &self.u), dot(v, &self.v), dot(v, &self.w))
+    }
+
+    /// Converts the vector `v`, given in terms of the basis, back to world space.
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        &self.u * v.x() + &self.v * v.y() + &self.w * v.z()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orthonormal() {
+        for w in [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+            Vec3::new(1.0, 2.0, 3.0).unit(),
+            Vec3::new(-0.3, 0.9, -0.1).unit(),
+        ] {
+            let onb = Onb::new(&w);
+
+            for axis in [&onb.u, &onb.v, &onb.w] {
+                assert!((axis.len() - 1.0).abs() < 1e-12);
+            }
+            assert!(dot(&onb.u, &onb.v).abs() < 1e-12);
+            assert!(dot(&onb.u, &onb.w).abs() < 1e-12);
+            assert!(dot(&onb.v, &onb.w).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let onb = Onb::new(&Vec3::new(0.2, -0.5, 0.7).unit());
+        let v = Vec3::new(1.0, 2.0, 3.0);
+        let res = onb.to_world(&onb.to_local(&v));
+
+        assert!((&res - &v).len() < 1e-12);
+    }
+
+    #[test]
+    fn test_local_z_is_w() {
+        let w = Vec3::new(0.2, -0.5, 0.7).unit();
+        let onb = Onb::new(&w);
+        let res = onb.to_world(&Vec3::new(0.0, 0.0, 1.0));
+
+        assert!((&res - &w).len() < 1e-12);
+    }
+}
@@ -0,0 +1,113 @@
+const MULTIPLIER: u64 = 0x5851f42d4c957f2d;
+const DEFAULT_STATE: u64 = 0x853c49e6748fea9b;
+const DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+/// O'Neill's PCG32 random number generator: small, fast, statistically strong, and able to jump
+/// ahead in its sequence in logarithmic time, so that any part of it can be reproduced without
+/// generating everything before it.
+#[derive(Debug, Clone)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    /// A generator on the `sequence`th of its 2^63 independent streams, started from `seed`.
+    pub fn new(sequence: u64, seed: u64) -> Self {
+        let mut rng = Pcg32 {
+            state: 0,
+            increment: (sequence << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state
+            .wrapping_mul(MULTIPLIER)
+            .wrapping_add(self.increment);
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// A uniform random number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        self.next_u32() as f64 * (1.0 / 4294967296.0)
+    }
+
+    /// Skips the next `delta` numbers, as Brown's "Random Number Generation with Arbitrary
+    /// Strides" describes.
+    pub fn advance(&mut self, delta: u64) {
+        let (mut multiplier, mut increment) = (MULTIPLIER, self.increment);
+        let (mut acc_multiplier, mut acc_increment) = (1_u64, 0_u64);
+        let mut delta = delta;
+        while delta > 0 {
+            if delta & 1 == 1 {
+                acc_multiplier = acc_multiplier.wrapping_mul(multiplier);
+                acc_increment = acc_increment
+                    .wrapping_mul(multiplier)
+                    .wrapping_add(increment);
+            }
+            increment = multiplier.wrapping_add(1).wrapping_mul(increment);
+            multiplier = multiplier.wrapping_mul(multiplier);
+            delta /= 2;
+        }
+        self.state = acc_multiplier
+            .wrapping_mul(self.state)
+            .wrapping_add(acc_increment);
+    }
+}
+
+impl Default for Pcg32 {
+    fn default() -> Self {
+        Pcg32 {
+            state: DEFAULT_STATE,
+            increment: DEFAULT_STREAM,
+        }
+    }
+}
+
+/// A finaliser that spreads every bit of `v` over the whole result, from Stafford's "Better Bit
+/// Mixing".
+pub fn mix_bits(mut v: u64) -> u64 {
+    v ^= v >> 31;
+    v = v.wrapping_mul(0x7fb5d329728ea185);
+    v ^= v >> 27;
+    v = v.wrapping_mul(0x81dadef4bc2dd44d);
+    v ^= v >> 33;
+    v
+}
+
+/// The hash of no values, which `hash_combine` starts from.
+pub const HASH_START: u64 = 0x9e3779b97f4a7c15;
+
+/// Folds `value` into the hash `h`, for hashing values one at a time as `hash` does.
+pub fn hash_combine(h: u64, value: u64) -> u64 {
+    mix_bits(h ^ mix_bits(value.wrapping_add(0x632be59bd9b4e019)))
+}
+
+/// Hashes `values` together, for deriving seeds from things like pixel coordinates.
+pub fn hash(values: &[u64]) -> u64 {
+    values.iter().fold(HASH_START, |h, v| hash_combine(h, *v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance() {
+        let mut rng = Pcg32::new(7, 42);
+        let mut skipped = rng.clone();
+        for _ in 0..1000 {
+            rng.next_u32();
+        }
+        skipped.advance(1000);
+
+        assert_eq!(rng.next_u32(), skipped.next_u32());
+    }
+}
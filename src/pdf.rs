@@ -0,0 +1,228 @@
+use std::f64::consts::PI;
+
+use crate::{
+    hittable::Hittable,
+    onb::Onb,
+    point::Point,
+    vec3::{dot, Vec3},
+};
+
+/// A distribution of directions that can be both sampled and evaluated, so that sampling
+/// strategies can be weighed against, or mixed with, each other.
+pub trait Pdf {
+    /// The density, per unit solid angle, of generating `direction`.
+    fn value(&self, direction: &Vec3) -> f64;
+
+    /// Draws a direction from the distribution, using two uniform random numbers in `[0, 1)`.
+    fn generate(&self, u: [f64; 2]) -> Vec3;
+}
+
+/// Directions on the hemisphere around a normal, distributed in proportion to the cosine of their
+/// angle with it. This matches the light scattered by a Lambertian surface.
+pub struct CosinePdf {
+    onb: Onb,
+}
+
+impl CosinePdf {
+    pub fn new(normal: &Vec3) -> Self {
+        CosinePdf {
+            onb: Onb::new(normal),
+        }
+    }
+}
+
+impl Pdf for CosinePdf {
+    fn value(&self, direction: &Vec3) -> f64 {
+        let cos_theta = dot(&direction.unit(), &self.onb.w);
+        cos_theta.max(0.0) / PI
+    }
+
+    fn generate(&self, u: [f64; 2]) -> Vec3 {
+        self.onb.to_world(&Vec3::random_cosine_direction(u))
+    }
+}
+
+/// Directions distributed uniformly over the whole sphere.
+pub struct SpherePdf;
+
+impl Pdf for SpherePdf {
+    fn value(&self, _: &Vec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self, u: [f64; 2]) -> Vec3 {
+        Vec3::on_unit_sphere(u)
+    }
+}
+
+/// Directions from `origin` towards a hittable, distributed as the hittable samples them when it's
+/// a light.
+pub struct HittablePdf<'a> {
+    origin: Point,
+    hittable: &'a dyn Hittable,
+}
+
+impl<'a> HittablePdf<'a> {
+    pub fn new(hittable: &'a dyn Hittable, origin: Point) -> Self {
+        HittablePdf { origin, hittable }
+    }
+}
+
+impl<'a> Pdf for HittablePdf<'a> {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.hittable.pdf_value(&self.origin, direction)
+    }
+
+    fn generate(&self, u: [f64; 2]) -> Vec3 {
+        self.hittable.random(&self.origin, u)
+    }
+}
+
+/// Mixes two distributions, drawing from the first with probability `weight` and from the second
+/// otherwise.
+///
+/// Dividing by the mixture's density weighs the two strategies with the balance heuristic using a
+/// single sample. The integrator instead takes a sample from each and weighs them with the power
+/// heuristic, which is less noisy, but needs both strategies to be evaluated separately.
+pub struct MixturePdf<'a> {
+    first: &'a dyn Pdf,
+    second: &'a dyn Pdf,
+    weight: f64,
+}
+
+impl<'a> MixturePdf<'a> {
+    pub fn new(first: &'a dyn Pdf, second: &'a dyn Pdf, weight: f64) -> Self {
+        MixturePdf {
+            first,
+            second,
+            weight,
+        }
+    }
+}
+
+impl<'a> Pdf for MixturePdf<'a> {
+    fn value(&self, direction: &Vec3) -> f64 {
+        self.weight * self.first.value(direction)
+            + (1.0 - self.weight) * self.second.value(direction)
+    }
+
+    fn generate(&self, u: [f64; 2]) -> Vec3 {
+        // rescale the number used to pick a distribution so it can be reused to sample from it
+        if u[0] < self.weight {
+            self.first.generate([u[0] / self.weight, u[1]])
+        } else {
+            let rescaled = (u[0] - self.weight) / (1.0 - self.weight);
+            self.second.generate([rescaled, u[1]])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color, interval::Interval, materials::lambertian::Lambertian, ray::Ray,
+        sphere::Sphere,
+    };
+
+    /// Integrates `pdf` over the sphere of directions with a midpoint rule.
+    fn integrate_sphere(pdf: &dyn Pdf) -> f64 {
+        let (n_theta, n_phi) = (400, 400);
+        let d_theta = PI / n_theta as f64;
+        let d_phi = 2.0 * PI / n_phi as f64;
+        let mut sum = 0.0;
+        for i in 0..n_theta {
+            let theta = (i as f64 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f64 + 0.5) * d_phi;
+                let direction = Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                );
+                sum += pdf.value(&direction) * theta.sin() * d_theta * d_phi;
+            }
+        }
+        sum
+    }
+
+    /// Averages `f` over directions generated from `pdf` on a stratified grid of samples.
+    fn mean_over_samples(pdf: &dyn Pdf, f: impl Fn(&Vec3) -> f64) -> f64 {
+        let n = 500;
+        let mut sum = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let u = [(i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64];
+                sum += f(&pdf.generate(u));
+            }
+        }
+        sum / (n * n) as f64
+    }
+
+    #[test]
+    fn test_sphere_pdf_normalised() {
+        assert!((integrate_sphere(&SpherePdf) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_cosine_pdf_normalised() {
+        let pdf = CosinePdf::new(&Vec3::new(1.0, 2.0, -1.0).unit());
+
+        assert!((integrate_sphere(&pdf) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_cosine_pdf_generates_cosine_distribution() {
+        // E[cos(theta)] = integral of cos^2(theta) / pi over the hemisphere = 2/3
+        let normal = Vec3::new(0.0, 1.0, 0.0);
+        let pdf = CosinePdf::new(&normal);
+        let res = mean_over_samples(&pdf, |direction| dot(direction, &normal));
+
+        assert!((res - 2.0 / 3.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_sphere_pdf_estimates_projected_solid_angle() {
+        // integral of cos(theta) over the hemisphere = pi, estimated as E[cos(theta) / pdf]
+        let normal = Vec3::new(0.0, 0.0, -1.0);
+        let res = mean_over_samples(&SpherePdf, |direction| {
+            dot(direction, &normal).max(0.0) / SpherePdf.value(direction)
+        });
+
+        assert!((res - PI).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_mixture_pdf_normalised() {
+        let cosine = CosinePdf::new(&Vec3::new(0.0, 0.0, 1.0));
+        let pdf = MixturePdf::new(&cosine, &SpherePdf, 0.3);
+
+        assert!((integrate_sphere(&pdf) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_mixture_of_cosine_and_light_estimates_irradiance() {
+        // a sphere of unit radiance, of radius 1 and 2 above a surface facing it, subtends a cone
+        // whose projected solid angle is pi * sin^2 of its half angle
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let light = Sphere::new(Point::new(0.0, 0.0, 2.0), 1.0, &grey);
+        let origin = Point::new(0.0, 0.0, 0.0);
+        let normal = Vec3::new(0.0, 0.0, 1.0);
+        let cosine = CosinePdf::new(&normal);
+        let towards_light = HittablePdf::new(&light, origin.clone());
+        let pdf = MixturePdf::new(&cosine, &towards_light, 0.5);
+
+        let irradiance = mean_over_samples(&pdf, |direction| {
+            let ray = Ray::new(origin.clone(), direction.clone(), 0.0);
+            if light
+                .hit(&ray, Interval::new(0.001, f64::INFINITY))
+                .is_none()
+            {
+                return 0.0;
+            }
+            dot(&direction.unit(), &normal) / pdf.value(direction)
+        });
+
+        assert!((irradiance - PI / 4.0).abs() < 1e-2, "{irradiance}");
+    }
+}
@@ -0,0 +1,100 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    description::Description,
+    point::Point,
+    vec3::{dot, Vec3},
+};
+
+const POINT_COUNT: usize = 256;
+
+/// Perlin gradient noise. The lattice is generated from `seed`, so the same seed always gives the
+/// same noise.
+pub struct Perlin {
+    random_vectors: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let random_vectors = (0..POINT_COUNT)
+            .map(|_| Vec3::on_unit_sphere([rng.gen(), rng.gen()]))
+            .collect();
+        let mut generate_perm = || {
+            let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+            perm.shuffle(&mut rng);
+            perm
+        };
+        let perm_x = generate_perm();
+        let perm_y = generate_perm();
+        let perm_z = generate_perm();
+        Perlin {
+            random_vectors,
+            perm_x,
+            perm_y,
+            perm_z,
+        }
+    }
+
+    /// Smoothly varying noise in roughly `[-1, 1]`.
+    pub fn noise(&self, p: &Point) -> f64 {
+        let u = p.x() - p.x().floor();
+        let v = p.y() - p.y().floor();
+        let w = p.z() - p.z().floor();
+        let i = p.x().floor() as i64;
+        let j = p.y().floor() as i64;
+        let k = p.z().floor() as i64;
+
+        // hermite smoothing of the interpolation weights avoids grid artefacts
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let index = self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize];
+                    let (fi, fj, fk) = (di as f64, dj as f64, dk as f64);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * dot(&self.random_vectors[index], &weight);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Sums `depth` octaves of noise, each at double the frequency and half the amplitude of the
+    /// last, and takes the absolute value.
+    pub fn turbulence(&self, p: &Point, depth: u32) -> f64 {
+        let mut accum = 0.0;
+        let mut p = p.clone();
+        let mut weight = 1.0;
+        for _ in 0..depth {
+            accum += weight * self.noise(&p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+        accum.abs()
+    }
+
+    /// Adds the noise's random gradients and permutations to `description`.
+    pub fn describe(&self, description: &mut Description) {
+        for vector in &self.random_vectors {
+            description.vec3(vector);
+        }
+        for perm in [&self.perm_x, &self.perm_y, &self.perm_z] {
+            for index in perm {
+                description.u64(*index as u64);
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::{path::PathBuf, time::Duration};
+
+/// Renders the image in passes that each add a few samples to every pixel, writing it out after
+/// each one, so that a long render can be looked at, or stopped, long before it's finished.
+#[derive(Debug, Clone)]
+pub struct Progressive {
+    /// The samples each pass adds to every pixel.
+    pub pass_samples: usize,
+    /// Stops the render, part way through a pass if need be, once this much time has passed.
+    pub time_budget: Option<Duration>,
+    /// Where the image is written after every pass, and when the render stops.
+    pub snapshot_path: PathBuf,
+    /// Where a checkpoint to resume the render from is written alongside each image, if set.
+    pub checkpoint_path: Option<PathBuf>,
+}
+
+impl Progressive {
+    pub fn new(pass_samples: usize, snapshot_path: impl Into<PathBuf>) -> Self {
+        Progressive {
+            pass_samples: pass_samples.max(1),
+            time_budget: None,
+            snapshot_path: snapshot_path.into(),
+            checkpoint_path: None,
+        }
+    }
+}
+
+/// Why a progressive render stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    /// Every pixel took the sampler's samples per pixel, or converged before then.
+    Finished,
+    OutOfTime,
+    Interrupted,
+}
@@ -0,0 +1,69 @@
+use std::f64::consts::PI;
+
+use crate::{description::Description, point::Point, vec3::Vec3};
+
+use super::projection::{CameraRay, Projection};
+
+/// A full 360° panorama, with longitude running across the image and latitude down it. The centre
+/// of the image looks straight ahead, the left and right edges straight behind, and the top and
+/// bottom edges straight up and down. Images should be twice as wide as they're tall.
+///
+/// Uses the same layout as `LatLong` environment maps, so a panorama of an empty scene reproduces
+/// its map.
+pub struct Equirectangular;
+
+impl Equirectangular {
+    pub fn new() -> Self {
+        Equirectangular
+    }
+}
+
+impl Default for Equirectangular {
+    fn default() -> Self {
+        Equirectangular::new()
+    }
+}
+
+impl Projection for Equirectangular {
+    fn generate_ray(
+        &self,
+        film: [f64; 2],
+        _lens: [f64; 2],
+        _aspect_ratio: f64,
+    ) -> Option<CameraRay> {
+        Some(CameraRay::new(Point::new(0.0, 0.0, 0.0), direction(film)))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("equirectangular");
+    }
+}
+
+/// The unit direction seen at `film`, turning right from straight ahead across the image.
+pub fn direction(film: [f64; 2]) -> Vec3 {
+    let longitude = (film[0] - 0.5) * 2.0 * PI;
+    let latitude = (0.5 - film[1]) * PI;
+    Vec3::new(
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+        -latitude.cos() * longitude.cos(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equirectangular() {
+        let expected = [
+            ([0.5, 0.5], Vec3::new(0.0, 0.0, -1.0)),
+            ([0.75, 0.5], Vec3::new(1.0, 0.0, 0.0)),
+            ([0.0, 0.5], Vec3::new(0.0, 0.0, 1.0)),
+            ([0.3, 0.0], Vec3::new(0.0, 1.0, 0.0)),
+        ];
+        for (film, expected) in expected {
+            assert!((&direction(film) - &expected).len() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{deg_to_radians, description::Description, point::Point, vec3::Vec3};
+
+use super::projection::{screen_position, CameraRay, Projection};
+
+/// An equidistant fisheye, where the angle away from the view direction grows in proportion to
+/// the distance from the centre of the image. The view fills a circle touching the top and bottom
+/// of the image, and the corners outside it are left black.
+pub struct Fisheye {
+    /// The angle at the edge of the circle, in radians.
+    half_fov: f64,
+}
+
+impl Fisheye {
+    /// `fov` is the angle across the circle, in degrees, up to 360.
+    pub fn new(fov: f64) -> Self {
+        Fisheye {
+            half_fov: deg_to_radians(fov.clamp(0.0, 360.0)) / 2.0,
+        }
+    }
+}
+
+impl Projection for Fisheye {
+    fn generate_ray(
+        &self,
+        film: [f64; 2],
+        _lens: [f64; 2],
+        aspect_ratio: f64,
+    ) -> Option<CameraRay> {
+        let (x, y) = screen_position(film, aspect_ratio);
+        let r = (x * x + y * y).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+        let theta = r * self.half_fov;
+        let phi = y.atan2(x);
+        let direction = Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            -theta.cos(),
+        );
+        Some(CameraRay::new(Point::new(0.0, 0.0, 0.0), direction))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("fisheye").f64(self.half_fov);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fisheye() {
+        let fisheye = Fisheye::new(180.0);
+
+        let centre = fisheye.generate_ray([0.5, 0.5], [0.5, 0.5], 2.0).unwrap();
+        assert!((&centre.direction - &Vec3::new(0.0, 0.0, -1.0)).len() < 1e-12);
+
+        // the top of the circle looks straight up, and halfway there looks halfway up
+        let top = fisheye.generate_ray([0.5, 0.0], [0.5, 0.5], 2.0).unwrap();
+        assert!((&top.direction - &Vec3::new(0.0, 1.0, 0.0)).len() < 1e-12);
+        let halfway = fisheye.generate_ray([0.5, 0.25], [0.5, 0.5], 2.0).unwrap();
+        assert!((&halfway.direction - &Vec3::new(0.0, 1.0, -1.0).unit()).len() < 1e-12);
+
+        assert!(fisheye.generate_ray([0.0, 0.0], [0.5, 0.5], 2.0).is_none());
+    }
+}
@@ -0,0 +1,81 @@
+use std::{fs, io, path::Path};
+
+/// One surface in a lens system: either a spherical interface between two media, or the aperture
+/// stop. Lengths are in metres.
+#[derive(Debug, Clone)]
+pub struct LensInterface {
+    /// The radius of curvature, positive when the centre of curvature lies towards the film, or 0
+    /// for the aperture stop.
+    pub radius: f64,
+    /// The distance along the optical axis to the next surface towards the film, or to the film
+    /// itself for the last surface.
+    pub thickness: f64,
+    /// The index of refraction of the medium between this surface and the next.
+    pub ior: f64,
+    pub aperture_radius: f64,
+}
+
+impl LensInterface {
+    pub fn is_stop(&self) -> bool {
+        self.radius == 0.0
+    }
+}
+
+/// The surfaces of a lens system, ordered from the front of the lens towards the film.
+///
+/// Prescriptions are read from text files in the format used by pbrt, with one surface per line
+/// giving its radius of curvature, thickness, index of refraction and aperture diameter, all in
+/// millimetres. The aperture stop has a radius of 0, and air an index of 0 or 1. Lines starting
+/// with `#` are comments.
+pub struct LensPrescription {
+    pub interfaces: Vec<LensInterface>,
+}
+
+impl LensPrescription {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        LensPrescription::parse(&String::from_utf8_lossy(&bytes))
+    }
+
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut interfaces = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let numbers = line
+                .split_whitespace()
+                .map(|token| {
+                    token
+                        .parse::<f64>()
+                        .map_err(|_| invalid_data(format!("expected a number, found {}", token)))
+                })
+                .collect::<io::Result<Vec<f64>>>()?;
+            let [radius, thickness, ior, aperture] = numbers[..] else {
+                return Err(invalid_data(format!(
+                    "expected 4 numbers per surface, found {}",
+                    numbers.len()
+                )));
+            };
+            if thickness < 0.0 || aperture <= 0.0 {
+                return Err(invalid_data(format!("invalid surface: {}", line)));
+            }
+            interfaces.push(LensInterface {
+                radius: radius / 1000.0,
+                thickness: thickness / 1000.0,
+                ior: if ior == 0.0 { 1.0 } else { ior },
+                aperture_radius: aperture / 2000.0,
+            });
+        }
+
+        if interfaces.is_empty() {
+            return Err(invalid_data("prescription has no surfaces".to_string()));
+        }
+        Ok(LensPrescription { interfaces })
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
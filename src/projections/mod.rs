@@ -0,0 +1,8 @@
+pub mod equirectangular;
+pub mod fisheye;
+pub mod lens_prescription;
+pub mod orthographic;
+pub mod perspective;
+pub mod projection;
+pub mod realistic;
+pub mod thin_lens;
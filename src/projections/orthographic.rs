@@ -0,0 +1,56 @@
+use crate::{description::Description, point::Point, vec3::Vec3};
+
+use super::projection::{screen_position, CameraRay, Projection};
+
+/// Parallel rays from a rectangle facing the view, so that things keep their size however far
+/// away they are. Suited to technical drawings.
+pub struct Orthographic {
+    half_height: f64,
+}
+
+impl Orthographic {
+    /// `view_height` is the height of the region the image covers, in world units.
+    pub fn new(view_height: f64) -> Self {
+        Orthographic {
+            half_height: view_height / 2.0,
+        }
+    }
+}
+
+impl Projection for Orthographic {
+    fn generate_ray(
+        &self,
+        film: [f64; 2],
+        _lens: [f64; 2],
+        aspect_ratio: f64,
+    ) -> Option<CameraRay> {
+        let (x, y) = screen_position(film, aspect_ratio);
+        let origin = Point::new(x * self.half_height, y * self.half_height, 0.0);
+        Some(CameraRay::new(origin, Vec3::new(0.0, 0.0, -1.0)))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("orthographic").f64(self.half_height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orthographic() {
+        let orthographic = Orthographic::new(4.0);
+
+        // every ray looks straight ahead, from a rectangle view_height tall
+        for (film, origin) in [
+            ([0.5, 0.5], Point::new(0.0, 0.0, 0.0)),
+            ([0.5, 0.0], Point::new(0.0, 2.0, 0.0)),
+            ([1.0, 1.0], Point::new(3.0, -2.0, 0.0)),
+        ] {
+            let ray = orthographic.generate_ray(film, [0.5, 0.5], 1.5).unwrap();
+            assert!((&ray.origin - &origin).len() < 1e-12);
+            assert_eq!(Vec3::new(0.0, 0.0, -1.0), ray.direction);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{deg_to_radians, description::Description, point::Point, vec3::Vec3};
+
+use super::projection::{screen_position, CameraRay, Projection};
+
+/// A pinhole camera, where rays fan out from a single point so that distant things look smaller.
+pub struct Perspective {
+    /// Half the height of the image plane one unit in front of the camera.
+    half_height: f64,
+}
+
+impl Perspective {
+    /// `vfov` is the vertical field of view, in degrees.
+    pub fn new(vfov: f64) -> Self {
+        Perspective {
+            half_height: (deg_to_radians(vfov) / 2.0).tan(),
+        }
+    }
+}
+
+impl Projection for Perspective {
+    fn generate_ray(
+        &self,
+        film: [f64; 2],
+        _lens: [f64; 2],
+        aspect_ratio: f64,
+    ) -> Option<CameraRay> {
+        let (x, y) = screen_position(film, aspect_ratio);
+        let direction = Vec3::new(x * self.half_height, y * self.half_height, -1.0);
+        Some(CameraRay::new(Point::new(0.0, 0.0, 0.0), direction))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description.kind("perspective").f64(self.half_height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perspective() {
+        let perspective = Perspective::new(90.0);
+
+        let centre = perspective
+            .generate_ray([0.5, 0.5], [0.5, 0.5], 2.0)
+            .unwrap();
+        assert_eq!(Point::new(0.0, 0.0, 0.0), centre.origin);
+        assert!((&centre.direction.unit() - &Vec3::new(0.0, 0.0, -1.0)).len() < 1e-12);
+
+        // the vertical field of view spans the height, and the width follows the aspect ratio
+        let top = perspective
+            .generate_ray([0.5, 0.0], [0.5, 0.5], 2.0)
+            .unwrap();
+        assert!((&top.direction.unit() - &Vec3::new(0.0, 1.0, -1.0).unit()).len() < 1e-12);
+        let corner = perspective
+            .generate_ray([1.0, 1.0], [0.5, 0.5], 2.0)
+            .unwrap();
+        assert!((&corner.direction.unit() - &Vec3::new(2.0, -1.0, -1.0).unit()).len() < 1e-12);
+    }
+}
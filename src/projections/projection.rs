@@ -0,0 +1,38 @@
+use crate::{description::Description, point::Point, vec3::Vec3};
+
+/// How a camera maps points on its image to rays. Rays are given in camera space, where the
+/// camera sits at the origin looking down -z, with +y up and +x to the right.
+pub trait Projection {
+    /// The ray through `film`, the point on the image where `[0, 0]` is the top left corner and
+    /// `[1, 1]` the bottom right, or None if the projection doesn't cover that point.
+    /// `aspect_ratio` is the image's width over its height, and `lens` picks where on the lens
+    /// the ray passes through, for projections that have one.
+    fn generate_ray(&self, film: [f64; 2], lens: [f64; 2], aspect_ratio: f64) -> Option<CameraRay>;
+
+    /// Adds the kind of projection and its parameters, its lens included, to `description`.
+    fn describe(&self, description: &mut Description);
+}
+
+/// A ray leaving the camera, in camera space.
+pub struct CameraRay {
+    pub origin: Point,
+    pub direction: Vec3,
+    /// How much the light arriving along the ray counts towards the image, for cameras that
+    /// don't pass light equally from every direction.
+    pub weight: f64,
+}
+
+impl CameraRay {
+    pub fn new(origin: Point, direction: Vec3) -> Self {
+        CameraRay {
+            origin,
+            direction,
+            weight: 1.0,
+        }
+    }
+}
+
+/// The position of `film` on a screen two units tall centred on the origin, with +y up.
+pub fn screen_position(film: [f64; 2], aspect_ratio: f64) -> (f64, f64) {
+    ((2.0 * film[0] - 1.0) * aspect_ratio, 1.0 - 2.0 * film[1])
+}
@@ -0,0 +1,355 @@
+use crate::{
+    description::Description,
+    interval::Interval,
+    materials::microfacet::refract,
+    point::Point,
+    ray::Ray,
+    samplers::low_discrepancy::radical_inverse,
+    vec3::{dot, Vec3},
+};
+
+use super::{
+    lens_prescription::{LensInterface, LensPrescription},
+    projection::{CameraRay, Projection},
+};
+
+/// The number of rings of the film the exit pupil is bounded separately for.
+const PUPIL_RINGS: usize = 64;
+/// The number of points on the rear element tested when bounding the exit pupil for each ring.
+const PUPIL_SAMPLES: usize = 256 * 256;
+
+/// A camera that traces rays through a real lens system, giving the vignetting, distortion,
+/// aberrations and bokeh of the lens, after Kolb et al.'s "A Realistic Camera Model for Computer
+/// Graphics" and pbrt's `RealisticCamera`.
+///
+/// The film sits at the camera's origin with the lens in front of it, and its image is flipped
+/// so that it appears the right way up. Rays leaving the camera start on its front element.
+pub struct Realistic {
+    /// Ordered from the front of the lens towards the film, with the last one's thickness set to
+    /// focus the lens.
+    interfaces: Vec<LensInterface>,
+    film_diagonal: f64,
+    /// For each ring of the film out from its centre, a box on the plane of the rear element
+    /// around where rays from points on the +x axis make it through the lens. Points elsewhere on
+    /// the ring rotate it to match.
+    exit_pupil_bounds: Vec<[Interval; 2]>,
+}
+
+impl Realistic {
+    /// Builds a camera from `prescription`, with the aperture stop opened to `aperture_diameter`
+    /// (in millimetres, and no wider than the prescription allows) and the film `film_diagonal`
+    /// millimetres across. The lens is moved to focus at `focus_distance` in front of the film.
+    ///
+    /// Returns None if the prescription can't pass light or can't focus that close.
+    pub fn new(
+        prescription: &LensPrescription,
+        aperture_diameter: f64,
+        focus_distance: f64,
+        film_diagonal: f64,
+    ) -> Option<Self> {
+        let mut interfaces = prescription.interfaces.clone();
+        for interface in interfaces.iter_mut().filter(|i| i.is_stop()) {
+            interface.aperture_radius = interface.aperture_radius.min(aperture_diameter / 2000.0);
+        }
+        let mut camera = Realistic {
+            interfaces,
+            film_diagonal: film_diagonal / 1000.0,
+            exit_pupil_bounds: Vec::new(),
+        };
+
+        let rear_thickness = camera.focus_thick_lens(focus_distance)?;
+        camera.interfaces.last_mut()?.thickness = rear_thickness;
+        camera.exit_pupil_bounds = (0..PUPIL_RINGS)
+            .map(|i| {
+                let ring = camera.film_diagonal / 2.0 / PUPIL_RINGS as f64;
+                camera.bound_exit_pupil(i as f64 * ring, (i + 1) as f64 * ring)
+            })
+            .collect();
+        Some(camera)
+    }
+
+    fn front_z(&self) -> f64 {
+        self.interfaces.iter().map(|i| i.thickness).sum()
+    }
+
+    fn rear_z(&self) -> f64 {
+        self.interfaces.last().unwrap().thickness
+    }
+
+    /// Traces a ray leaving the film through the lens, returning it as it leaves the front
+    /// element, or None if it's blocked.
+    fn trace_from_film(&self, ray: &Ray) -> Option<Ray> {
+        let mut ray = Ray::new(ray.origin.clone(), ray.direction.clone(), ray.time);
+        let mut z = 0.0;
+        for (i, interface) in self.interfaces.iter().enumerate().rev() {
+            z -= interface.thickness;
+            let ior_before = interface.ior;
+            let ior_after = if i > 0 {
+                self.interfaces[i - 1].ior
+            } else {
+                1.0
+            };
+            ray = self.cross_interface(interface, z, &ray, ior_after / ior_before)?;
+        }
+        Some(ray)
+    }
+
+    /// Traces a ray from the scene through the lens, returning it as it leaves the rear element,
+    /// or None if it's blocked.
+    fn trace_from_scene(&self, ray: &Ray) -> Option<Ray> {
+        let mut ray = Ray::new(ray.origin.clone(), ray.direction.clone(), ray.time);
+        let mut z = -self.front_z();
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            let ior_before = if i > 0 {
+                self.interfaces[i - 1].ior
+            } else {
+                1.0
+            };
+            ray = self.cross_interface(interface, z, &ray, interface.ior / ior_before)?;
+            z += interface.thickness;
+        }
+        Some(ray)
+    }
+
+    /// Moves `ray` to where it meets the surface at `z` on the axis and refracts it through,
+    /// where `eta` is the index of refraction it passes into relative to the one it's leaving.
+    fn cross_interface(
+        &self,
+        interface: &LensInterface,
+        z: f64,
+        ray: &Ray,
+        eta: f64,
+    ) -> Option<Ray> {
+        let (t, normal) = if interface.is_stop() {
+            let t = (z - ray.origin.z()) / ray.direction.z();
+            if !t.is_finite() || t < 0.0 {
+                return None;
+            }
+            (t, None)
+        } else {
+            let (t, normal) = intersect_spherical(interface.radius, z + interface.radius, ray)?;
+            (t, Some(normal))
+        };
+
+        let point = ray.at(t);
+        let r_sq = point.x() * point.x() + point.y() * point.y();
+        if r_sq > interface.aperture_radius * interface.aperture_radius {
+            return None;
+        }
+        let direction = match normal {
+            Some(normal) => refract(&-ray.direction.unit(), &normal, eta)?.0,
+            None => ray.direction.clone(),
+        };
+        Some(Ray::new(point, direction, ray.time))
+    }
+
+    /// Finds the film distance that brings `focus_distance` into focus, by treating the lens as
+    /// a thick lens whose principal planes and focal points are found by tracing rays parallel to
+    /// the axis through either side.
+    fn focus_thick_lens(&self, focus_distance: f64) -> Option<f64> {
+        let x = 0.001 * self.film_diagonal;
+        let from_scene = Ray::new(
+            Point::new(x, 0.0, -self.front_z() - 1.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            0.0,
+        );
+        let (principal0, focal0) =
+            cardinal_points(&from_scene, &self.trace_from_scene(&from_scene)?)?;
+        let from_film = Ray::new(
+            Point::new(x, 0.0, 1.0 - self.rear_z()),
+            Vec3::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let (principal1, _) = cardinal_points(&from_film, &self.trace_from_film(&from_film)?)?;
+
+        // solve the thick lens equation for how far to move the film from where it was
+        let f = focal0 - principal0;
+        let z = -focus_distance;
+        let c = (principal1 - z - principal0) * (principal1 - z - 4.0 * f - principal0);
+        if c <= 0.0 {
+            return None;
+        }
+        let delta = 0.5 * (principal1 - z + principal0 - c.sqrt());
+        Some(self.rear_z() + delta)
+    }
+
+    /// Bounds the exit pupil for film points between `film_x0` and `film_x1` on the +x axis, by
+    /// tracing rays from them towards points on a grid around the rear element.
+    fn bound_exit_pupil(&self, film_x0: f64, film_x1: f64) -> [Interval; 2] {
+        let rear_radius = self.interfaces.last().unwrap().aperture_radius;
+        let extent = 1.5 * rear_radius;
+        let mut bounds = [Interval::empty(), Interval::empty()];
+        let mut exiting = 0;
+        for i in 0..PUPIL_SAMPLES {
+            let t = (i as f64 + 0.5) / PUPIL_SAMPLES as f64;
+            let film = Point::new(film_x0 + t * (film_x1 - film_x0), 0.0, 0.0);
+            let x = -extent + 2.0 * extent * radical_inverse(2, i as u64);
+            let y = -extent + 2.0 * extent * radical_inverse(3, i as u64);
+            let inside = bounds[0].contains(x) && bounds[1].contains(y);
+            let rear = Point::new(x, y, -self.rear_z());
+            let ray = Ray::new(film.clone(), rear - film, 0.0);
+            if inside || self.trace_from_film(&ray).is_some() {
+                bounds = [
+                    Interval::enclosing(&bounds[0], &Interval::new(x, x)),
+                    Interval::enclosing(&bounds[1], &Interval::new(y, y)),
+                ];
+                exiting += 1;
+            }
+        }
+
+        if exiting == 0 {
+            return [
+                Interval::new(-extent, extent),
+                Interval::new(-extent, extent),
+            ];
+        }
+        // pad by the grid spacing, so that gaps between the points tested aren't missed
+        let padding = 2.0 * (2.0 * extent * 2.0_f64.sqrt()) / (PUPIL_SAMPLES as f64).sqrt();
+        bounds.map(|interval| interval.expand(2.0 * padding))
+    }
+
+    /// A point on the rear element for a ray from `film` to pass through, and the area of the
+    /// bounds it was sampled from.
+    fn sample_exit_pupil(&self, film: &Point, u: [f64; 2]) -> (Point, f64) {
+        let r = (film.x() * film.x() + film.y() * film.y()).sqrt();
+        let ring = (r / (self.film_diagonal / 2.0) * PUPIL_RINGS as f64) as usize;
+        let [x_bounds, y_bounds] = &self.exit_pupil_bounds[ring.min(PUPIL_RINGS - 1)];
+        let x = x_bounds.min + u[0] * x_bounds.size();
+        let y = y_bounds.min + u[1] * y_bounds.size();
+
+        // rotate from the +x axis round to the film point
+        let (sin, cos) = if r > 0.0 {
+            (film.y() / r, film.x() / r)
+        } else {
+            (0.0, 1.0)
+        };
+        let point = Point::new(cos * x - sin * y, sin * x + cos * y, -self.rear_z());
+        (point, x_bounds.size() * y_bounds.size())
+    }
+}
+
+impl Projection for Realistic {
+    fn generate_ray(&self, film: [f64; 2], lens: [f64; 2], aspect_ratio: f64) -> Option<CameraRay> {
+        // the lens turns the image upside down and back to front, so flip the film to match
+        let width = self.film_diagonal / (1.0 + 1.0 / (aspect_ratio * aspect_ratio)).sqrt();
+        let height = width / aspect_ratio;
+        let film = Point::new((0.5 - film[0]) * width, (film[1] - 0.5) * height, 0.0);
+
+        let (rear, bounds_area) = self.sample_exit_pupil(&film, lens);
+        let from_film = Ray::new(film.clone(), rear - film, 0.0);
+        let ray = self.trace_from_film(&from_film)?;
+
+        // the cos^4 falloff of light reaching the film at an angle, and the rays each sample
+        // stands for, relative to the centre of the film
+        let cos_theta = -from_film.direction.unit().z();
+        let [x_bounds, y_bounds] = &self.exit_pupil_bounds[0];
+        let centre_area = x_bounds.size() * y_bounds.size();
+        let weight = cos_theta.powi(4) * bounds_area / centre_area;
+        Some(CameraRay {
+            origin: ray.origin,
+            direction: ray.direction,
+            weight,
+        })
+    }
+
+    fn describe(&self, description: &mut Description) {
+        // the exit pupil is found from the rest, so needn't be described
+        description.kind("realistic").f64(self.film_diagonal);
+        for interface in &self.interfaces {
+            description.f64s(&[
+                interface.radius,
+                interface.thickness,
+                interface.ior,
+                interface.aperture_radius,
+            ]);
+        }
+    }
+}
+
+/// Intersects `ray` with the sphere of `radius` centred on the axis at `z_centre`, picking
+/// whichever of its two hits lies on the lens surface. Returns the distance along the ray and
+/// the normal, facing back towards where the ray came from.
+fn intersect_spherical(radius: f64, z_centre: f64, ray: &Ray) -> Option<(f64, Vec3)> {
+    let oc = &ray.origin - &Point::new(0.0, 0.0, z_centre);
+    let a = ray.direction.len_sq();
+    let h = dot(&ray.direction, &oc);
+    let c = oc.len_sq() - radius * radius;
+    let discriminant = h * h - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t0 = (-h - discriminant.sqrt()) / a;
+    let t1 = (-h + discriminant.sqrt()) / a;
+
+    // the surface is the half of the sphere nearer whichever way the ray is coming from
+    let use_closer = (ray.direction.z() > 0.0) != (radius < 0.0);
+    let t = if use_closer { t0 } else { t1 };
+    if t < 0.0 {
+        return None;
+    }
+    let normal = (oc + &ray.direction * t).unit();
+    if dot(&normal, &ray.direction) > 0.0 {
+        return Some((t, -normal));
+    }
+    Some((t, normal))
+}
+
+/// The z of the principal plane and focal point found by tracing `incoming`, a ray parallel to
+/// the axis, through the lens to `outgoing`.
+fn cardinal_points(incoming: &Ray, outgoing: &Ray) -> Option<(f64, f64)> {
+    if outgoing.direction.x() == 0.0 {
+        return None;
+    }
+    let focal_t = -outgoing.origin.x() / outgoing.direction.x();
+    let principal_t = (incoming.origin.x() - outgoing.origin.x()) / outgoing.direction.x();
+    Some((outgoing.at(principal_t).z(), outgoing.at(focal_t).z()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A double Gauss lens of 50mm focal length, from Smith's "Modern Lens Design".
+    const DOUBLE_GAUSS: &str = "
+        # radius  thickness  ior  aperture
+        29.475    3.76       1.67   25.2
+        84.83     0.12       1      25.2
+        19.275    4.025      1.67   23
+        40.77     3.275      1.699  23
+        12.75     5.705      1      18
+        0         4.5        0      17.1
+        -14.495   1.18       1.603  17
+        40.77     6.065      1.658  20
+        -20.385   0.19       1      20
+        437.065   3.22       1.717  20
+        -39.73    5          1      20
+    ";
+
+    #[test]
+    fn test_realistic_focus() {
+        let prescription = LensPrescription::parse(DOUBLE_GAUSS).unwrap();
+        let camera = Realistic::new(&prescription, 10.0, 2.0, 35.0).unwrap();
+
+        // rays from the centre of the film through different parts of the lens meet the axis
+        // again around the focus distance
+        let mut crossings = 0;
+        for u in [[0.3, 0.5], [0.4, 0.5], [0.6, 0.5], [0.7, 0.5]] {
+            let Some(ray) = camera.generate_ray([0.5, 0.5], u, 1.5) else {
+                continue;
+            };
+            assert!(ray.weight > 0.9);
+            let t = -ray.origin.x() / ray.direction.x();
+            let z = ray.origin.z() + t * ray.direction.z();
+            assert!((z + 2.0).abs() < 0.05, "crossed the axis at {}", z);
+            crossings += 1;
+        }
+        assert!(crossings > 0);
+
+        // the corners of the film are dimmer
+        let corner = (0..100)
+            .filter_map(|i| camera.generate_ray([0.01, 0.01], [i as f64 / 100.0, 0.5], 1.5))
+            .next()
+            .unwrap();
+        assert!(corner.weight < 1.0);
+    }
+}
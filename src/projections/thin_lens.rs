@@ -0,0 +1,150 @@
+use crate::{
+    apertures::aperture::Aperture,
+    deg_to_radians,
+    description::Description,
+    point::Point,
+    vec3::{dot, Vec3},
+};
+
+use super::projection::{screen_position, CameraRay, Projection};
+
+/// A perspective camera with an ideal thin lens, so that only things on the plane of focus are
+/// sharp and everything else blurs into the shape of the aperture.
+///
+/// Like a view camera, the lens can be tilted to turn the plane of focus away from parallel with
+/// the film (the Scheimpflug principle), and shifted to move the view without turning the camera,
+/// which keeps vertical lines vertical.
+pub struct ThinLens {
+    half_height: f64,
+    lens_radius: f64,
+    focus_distance: f64,
+    aperture: Box<dyn Aperture>,
+    /// The normal of the plane of focus.
+    focus_normal: Vec3,
+    shift: [f64; 2],
+}
+
+impl ThinLens {
+    /// `vfov` is the vertical field of view in degrees, and `focus_distance` how far in front of
+    /// the camera the plane of focus is. A zero `aperture_diameter` keeps everything in focus.
+    pub fn new(
+        vfov: f64,
+        aperture_diameter: f64,
+        focus_distance: f64,
+        aperture: Box<dyn Aperture>,
+    ) -> Self {
+        ThinLens::with_tilt_shift(
+            vfov,
+            aperture_diameter,
+            focus_distance,
+            aperture,
+            [0.0, 0.0],
+            [0.0, 0.0],
+        )
+    }
+
+    /// A lens tilted by `tilt`, in degrees, and shifted by `shift`, in fractions of the image's
+    /// height.
+    ///
+    /// The first angle of `tilt` turns the plane of focus about the camera's horizontal axis, so
+    /// that it recedes towards the bottom of the image, as for bringing the whole of a tabletop
+    /// into focus. The second turns it about the vertical axis, so that it recedes towards the
+    /// right. It still passes through the point `focus_distance` straight ahead.
+    pub fn with_tilt_shift(
+        vfov: f64,
+        aperture_diameter: f64,
+        focus_distance: f64,
+        aperture: Box<dyn Aperture>,
+        tilt: [f64; 2],
+        shift: [f64; 2],
+    ) -> Self {
+        let (tilt_x, tilt_y) = (deg_to_radians(tilt[0]), deg_to_radians(tilt[1]));
+        let focus_normal = Vec3::new(
+            tilt_y.sin() * tilt_x.cos(),
+            -tilt_x.sin(),
+            tilt_y.cos() * tilt_x.cos(),
+        );
+        ThinLens {
+            half_height: (deg_to_radians(vfov) / 2.0).tan(),
+            lens_radius: aperture_diameter / 2.0,
+            focus_distance,
+            aperture,
+            focus_normal,
+            shift,
+        }
+    }
+}
+
+impl Projection for ThinLens {
+    fn generate_ray(&self, film: [f64; 2], lens: [f64; 2], aspect_ratio: f64) -> Option<CameraRay> {
+        let (x, y) = screen_position(film, aspect_ratio);
+        let (x, y) = (x + 2.0 * self.shift[0], y + 2.0 * self.shift[1]);
+        let direction = Vec3::new(x * self.half_height, y * self.half_height, -1.0);
+        if self.lens_radius <= 0.0 {
+            return Some(CameraRay::new(Point::new(0.0, 0.0, 0.0), direction));
+        }
+
+        // every ray through the lens from this point on the film meets the ray through the centre
+        // of the lens on the plane of focus
+        let [lens_x, lens_y] = self.aperture.sample(lens);
+        let origin = Point::new(lens_x * self.lens_radius, lens_y * self.lens_radius, 0.0);
+        let focus_centre = Point::new(0.0, 0.0, -self.focus_distance);
+        let t = dot(&self.focus_normal, &focus_centre) / dot(&self.focus_normal, &direction);
+        if !t.is_finite() || t <= 0.0 {
+            // the plane of focus is never reached, so this direction is focused at infinity
+            return Some(CameraRay::new(origin, direction));
+        }
+        let focus = &direction * t;
+        let direction = focus - origin.clone();
+        Some(CameraRay::new(origin, direction))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("thin lens")
+            .f64s(&[
+                self.half_height,
+                self.lens_radius,
+                self.focus_distance,
+                self.shift[0],
+                self.shift[1],
+            ])
+            .vec3(&self.focus_normal);
+        self.aperture.describe(description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apertures::circular::Circular;
+
+    #[test]
+    fn test_tilted_focus() {
+        // tilted so that the plane of focus runs through z = -1 + y
+        let lens = ThinLens::with_tilt_shift(
+            60.0,
+            0.1,
+            1.0,
+            Box::new(Circular::new()),
+            [45.0, 0.0],
+            [0.0, 0.0],
+        );
+        let film = [0.5, 0.8];
+        let rays: Vec<CameraRay> = [[0.1, 0.2], [0.9, 0.5], [0.4, 0.95]]
+            .into_iter()
+            .map(|u| lens.generate_ray(film, u, 1.0).unwrap())
+            .collect();
+
+        // all of the rays meet at the same point on the plane z = -1 + y
+        let meet = |ray: &CameraRay| {
+            let t =
+                (-1.0 + ray.origin.y() - ray.origin.z()) / (ray.direction.z() - ray.direction.y());
+            &ray.origin + &(&ray.direction * t)
+        };
+        let first = meet(&rays[0]);
+        for ray in &rays[1..] {
+            assert!((&meet(ray) - &first).len() < 1e-9);
+        }
+    }
+}
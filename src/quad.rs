@@ -0,0 +1,117 @@
+use std::f64::consts::PI;
+
+use crate::{
+    aabb::Aabb,
+    color::luminance,
+    description::Description,
+    hit_record::HitRecord,
+    hittable::Hittable,
+    interval::Interval,
+    materials::material::Material,
+    point::Point,
+    ray::Ray,
+    vec3::{cross, dot, Vec3},
+};
+
+/// A parallelogram with one corner at `q`, spanned by the edge vectors `u` and `v`.
+pub struct Quad<'a> {
+    q: Point,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    /// Plane constant, such that `dot(normal, p) = d` for every point `p` on the quad's plane
+    d: f64,
+    /// `n / dot(n, n)` for the unnormalised normal `n`, used to find a point's planar coordinates
+    w: Vec3,
+    area: f64,
+    material: &'a dyn Material,
+}
+
+impl<'a> Quad<'a> {
+    pub fn new(q: Point, u: Vec3, v: Vec3, material: &'a dyn Material) -> Self {
+        let n = cross(u.clone(), v.clone());
+        let normal = n.unit();
+        let d = dot(&normal, &q);
+        let w = &n / n.len_sq();
+        Quad {
+            q,
+            u,
+            v,
+            normal,
+            d,
+            w,
+            area: n.len(),
+            material,
+        }
+    }
+}
+
+impl<'a> Hittable for Quad<'a> {
+    fn bounding_box(&self) -> Aabb {
+        let far = &(&self.q + &self.u) + &self.v;
+        let diagonal1 = Aabb::from_points(&self.q, &far);
+        let diagonal2 = Aabb::from_points(&(&self.q + &self.u), &(&self.q + &self.v));
+        Aabb::enclosing(&diagonal1, &diagonal2).pad(1e-4)
+    }
+
+    fn power(&self) -> f64 {
+        // emitted from both sides
+        luminance(&self.material.emitted()) * 2.0 * self.area * PI
+    }
+
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord<'_>> {
+        let denominator = dot(&self.normal, &ray.direction);
+        if denominator.abs() < 1e-8 {
+            return None; // the ray is parallel to the plane
+        }
+
+        let t = (self.d - dot(&self.normal, &ray.origin)) / denominator;
+        if !ray_t.contains(t) {
+            return None;
+        }
+
+        // express the hit point in terms of the edge vectors to check it lies within the quad
+        let point = ray.at(t);
+        let planar_hit = &point - &self.q;
+        let alpha = dot(&self.w, &cross(planar_hit.clone(), self.v.clone()));
+        let beta = dot(&self.w, &cross(self.u.clone(), planar_hit));
+        let unit = Interval::new(0.0, 1.0);
+        if !unit.contains(alpha) || !unit.contains(beta) {
+            return None;
+        }
+
+        let front_face = denominator < 0.0;
+        let normal = if front_face {
+            self.normal.clone()
+        } else {
+            -self.normal.clone()
+        };
+        Some(HitRecord::new(point, normal, t, front_face, self.material))
+    }
+
+    fn pdf_value(&self, origin: &Point, direction: &Vec3) -> f64 {
+        let ray = Ray::new(origin.clone(), direction.clone(), 0.0);
+        let Some(hit) = self.hit(&ray, Interval::new(0.001, f64::INFINITY)) else {
+            return 0.0;
+        };
+
+        // convert the uniform area density to solid angle
+        let distance_sq = hit.t * hit.t * direction.len_sq();
+        let cos_theta = dot(&direction.unit(), &self.normal).abs();
+        distance_sq / (cos_theta * self.area)
+    }
+
+    fn random(&self, origin: &Point, u: [f64; 2]) -> Vec3 {
+        let point = &self.q + &(&self.u * u[0]) + &self.v * u[1];
+        point - origin.clone()
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("quad")
+            .vec3(&self.q)
+            .vec3(&self.u)
+            .vec3(&self.v);
+        self.material.describe(description);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    deg_to_radians,
+    vec3::{cross, dot, Vec3},
+};
+
+/// A unit quaternion representing a rotation, stored as its scalar part `w` and vector part `v`.
+#[derive(Debug, Clone)]
+pub struct Quaternion {
+    pub w: f64,
+    pub v: Vec3,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Quaternion {
+            w: 1.0,
+            v: Vec3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// A rotation of `angle` degrees about `axis`, anticlockwise when looking down the axis
+    /// towards the origin.
+    pub fn from_axis_angle(axis: &Vec3, angle: f64) -> Self {
+        let half_angle = deg_to_radians(angle) / 2.0;
+        Quaternion {
+            w: half_angle.cos(),
+            v: axis.unit() * half_angle.sin(),
+        }
+    }
+
+    /// The rotation taking the standard axes to the orthonormal, right-handed `columns`, using
+    /// Shepperd's method of picking the largest component to divide by for stability.
+    pub fn from_rotation_matrix(columns: &[Vec3; 3]) -> Self {
+        let m = |row: usize, col: usize| columns[col][row];
+        let trace = m(0, 0) + m(1, 1) + m(2, 2);
+        let (w, x, y, z) = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            (
+                s / 4.0,
+                (m(2, 1) - m(1, 2)) / s,
+                (m(0, 2) - m(2, 0)) / s,
+                (m(1, 0) - m(0, 1)) / s,
+            )
+        } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+            let s = (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * 2.0;
+            (
+                (m(2, 1) - m(1, 2)) / s,
+                s / 4.0,
+                (m(0, 1) + m(1, 0)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+            )
+        } else if m(1, 1) > m(2, 2) {
+            let s = (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * 2.0;
+            (
+                (m(0, 2) - m(2, 0)) / s,
+                (m(0, 1) + m(1, 0)) / s,
+                s / 4.0,
+                (m(1, 2) + m(2, 1)) / s,
+            )
+        } else {
+            let s = (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * 2.0;
+            (
+                (m(1, 0) - m(0, 1)) / s,
+                (m(0, 2) + m(2, 0)) / s,
+                (m(1, 2) + m(2, 1)) / s,
+                s / 4.0,
+            )
+        };
+        Quaternion {
+            w,
+            v: Vec3::new(x, y, z),
+        }
+        .normalised()
+    }
+
+    fn normalised(&self) -> Self {
+        let len = (self.w * self.w + self.v.len_sq()).sqrt();
+        Quaternion {
+            w: self.w / len,
+            v: &self.v / len,
+        }
+    }
+
+    /// The opposite rotation.
+    pub fn inverse(&self) -> Self {
+        Quaternion {
+            w: self.w,
+            v: -self.v.clone(),
+        }
+    }
+
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        // v' = v + 2w(q x v) + 2q x (q x v), expanding q v q*
+        let t = cross(self.v.clone(), v.clone()) * 2.0;
+        v + &(&t * self.w) + cross(self.v.clone(), t)
+    }
+
+    /// The angle, in radians, that the rotation from `self` to `other` turns through.
+    pub fn angle_to(&self, other: &Quaternion) -> f64 {
+        let cos_half = (self.w * other.w + dot(&self.v, &other.v)).abs().min(1.0);
+        2.0 * cos_half.acos()
+    }
+
+    /// Spherical linear interpolation, turning from `a` at `t = 0` to `b` at `t = 1` at a constant
+    /// angular speed. Takes the shorter way round, so `a` and `b` must be less than half a turn
+    /// apart to spin the way they're meant to.
+    pub fn slerp(a: &Quaternion, b: &Quaternion, t: f64) -> Self {
+        let mut cos_theta = a.w * b.w + dot(&a.v, &b.v);
+        let b = if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            Quaternion {
+                w: -b.w,
+                v: -b.v.clone(),
+            }
+        } else {
+            b.clone()
+        };
+
+        // nearly parallel quaternions would divide by almost nothing, but lerp well enough
+        let (weight_a, weight_b) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1.0 - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta,
+            )
+        };
+        Quaternion {
+            w: a.w * weight_a + b.w * weight_b,
+            v: &a.v * weight_a + &b.v * weight_b,
+        }
+        .normalised()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slerp() {
+        let a = Quaternion::identity();
+        let b = Quaternion::from_axis_angle(&Vec3::new(0.0, 1.0, 0.0), 90.0);
+        let halfway = Quaternion::slerp(&a, &b, 0.5);
+
+        // a quarter turn about +y takes +z to +x, so an eighth of one lands in between
+        let res = halfway.rotate(&Vec3::new(0.0, 0.0, 1.0));
+        let expected = Vec3::new(1.0, 0.0, 1.0).unit();
+        assert!((&res - &expected).len() < 1e-12);
+        assert!((a.angle_to(&halfway) - deg_to_radians(45.0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_from_rotation_matrix() {
+        let q = Quaternion::from_axis_angle(&Vec3::new(1.0, -2.0, 0.5), 130.0);
+        let columns = [
+            q.rotate(&Vec3::new(1.0, 0.0, 0.0)),
+            q.rotate(&Vec3::new(0.0, 1.0, 0.0)),
+            q.rotate(&Vec3::new(0.0, 0.0, 1.0)),
+        ];
+        let res = Quaternion::from_rotation_matrix(&columns);
+
+        assert!(q.angle_to(&res) < 1e-6);
+    }
+}
@@ -1,16 +1,46 @@
 use crate::{
-    color::Color, hit_record::HitRecord, hittable::Hittable, interval::Interval, point::Point,
+    color::Color,
+    hit_record::HitRecord,
+    hittable::Hittable,
+    integrator::Integrator,
+    interval::Interval,
+    light_samplers::light_sampler::LightSampler,
+    materials::material::bsdf_sample_u,
+    pcg::{hash, Pcg32},
+    point::Point,
+    samplers::sampler::Sampler,
+    scene::Scene,
     vec3::Vec3,
 };
 
 pub struct Ray {
     pub origin: Point,
     pub direction: Vec3,
+    /// The moment the ray exists at, which moving hittables are positioned by.
+    pub time: f64,
+    /// Identifies the camera sample the ray belongs to, which seeds the random decisions hittables
+    /// make of their own. Every ray spawned along the sample's path carries it on.
+    pub sample_seed: u64,
 }
 
 impl Ray {
-    pub fn new(origin: Point, direction: Vec3) -> Self {
-        Ray { origin, direction }
+    pub fn new(origin: Point, direction: Vec3, time: f64) -> Self {
+        Ray {
+            origin,
+            direction,
+            time,
+            sample_seed: 0,
+        }
+    }
+
+    /// A ray from `origin` along `direction` at the same time and for the same sample as this one.
+    pub fn spawn(&self, origin: Point, direction: Vec3) -> Self {
+        Ray {
+            origin,
+            direction,
+            time: self.time,
+            sample_seed: self.sample_seed,
+        }
     }
 
     // Given a scalar value `t`, returns the point along the ray reached by traversing its direction
@@ -21,49 +51,391 @@ impl Ray {
         &self.origin + &scale
     }
 
-    pub fn color(&self, hittables: &[&dyn Hittable], max_reflections: i32) -> Color {
-        if max_reflections <= 0 {
-            // Once we've hit the maximum number of reflections, contribute no more light to the
-            // scene
-            return Color::new(0.0, 0.0, 0.0);
+    /// A random number generator for hittables that make random decisions of their own, like where
+    /// in a medium the ray scatters, without a sampler having to be threaded through every hit
+    /// test. It's seeded by the ray's sample, the ray itself, `distance` along it and `stream`, so
+    /// the numbers are as reproducible as the rest of the render, differ from sample to sample,
+    /// and calls that mustn't share numbers can ask for different streams.
+    pub fn rng(&self, distance: f64, stream: u64) -> Pcg32 {
+        let mut values = [0; 10];
+        for axis in 0..3 {
+            values[axis] = self.origin[axis].to_bits();
+            values[axis + 3] = self.direction[axis].to_bits();
         }
-        let white = Color::new(1.0, 1.0, 1.0);
-        let blue = Color::new(0.5, 0.7, 1.0);
+        values[6] = self.time.to_bits();
+        values[7] = distance.to_bits();
+        values[8] = self.sample_seed;
+        values[9] = stream;
+        Pcg32::new(hash(&values), stream)
+    }
 
-        let hit_option = hit_any(hittables, self, Interval::new(0.001, std::f64::INFINITY));
-        if let Some(hit) = hit_option {
-            let (scattered_ray, reflection_color) = hit.material.scatter(&self, &hit);
-            return reflection_color * scattered_ray.color(hittables, max_reflections - 1);
+    /// Estimates the radiance arriving along the ray by following a single path through the
+    /// scene, gathering emitted light at each bounce. Once the path is `rr_min_depth` bounces
+    /// long, it is terminated at random with a probability that grows as its throughput falls,
+    /// and the surviving paths are reweighted to keep the estimate unbiased.
+    ///
+    /// `light_sampler` chooses which light to sample at each bounce, and must have been built for
+    /// `scene`. Every random decision along the path takes the next dimensions of `sampler`.
+    pub fn color(
+        &self,
+        scene: &Scene,
+        integrator: &Integrator,
+        light_sampler: &dyn LightSampler,
+        sampler: &mut dyn Sampler,
+    ) -> Color {
+        let mut color = Color::new(0.0, 0.0, 0.0);
+        // the fraction of the light arriving along `ray` that makes it back to the camera
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = self.spawn(self.origin.clone(), self.direction.clone());
+        // the density `ray` was sampled with at the previous bounce, or None if light sampling
+        // couldn't also have found it (camera rays and delta lobes). used to avoid counting
+        // emission that was already accounted for by sampling the lights
+        let mut bsdf_pdf: Option<f64> = None;
+
+        for depth in 0..integrator.max_depth {
+            let hit_option = hit_any(scene.hittables, &ray, Interval::new(0.001, f64::INFINITY));
+            let Some((hit, hittable)) = hit_option else {
+                let environment = scene.environment;
+                let light_pdf = environment.can_sample().then(|| {
+                    let index = scene.light_count() - 1;
+                    environment.pdf(&ray.direction) * light_sampler.pmf(&ray.origin, index)
+                });
+                let weight = emission_weight(integrator, bsdf_pdf, light_pdf);
+                color += &throughput * &(environment.radiance(&ray.direction) * weight);
+                break;
+            };
+
+            let emitted = hit.material.emitted();
+            if !emitted.is_near_zero() {
+                let light_pdf = scene.light_index(hittable).map(|index| {
+                    let pdf = scene.lights[index].pdf_value(&ray.origin, &ray.direction);
+                    pdf * light_sampler.pmf(&ray.origin, index)
+                });
+                let weight = emission_weight(integrator, bsdf_pdf, light_pdf);
+                color += &throughput * &(emitted * weight);
+            }
+
+            let wo = -ray.direction.unit();
+            color += &throughput
+                * &sample_lights(scene, integrator, light_sampler, sampler, &wo, &hit, &ray);
+
+            let sample = hit.material.sample(&wo, &hit, bsdf_sample_u(sampler));
+            let Some(sample) = sample else {
+                break; // the path was absorbed
+            };
+            throughput = throughput * sample.weight();
+            bsdf_pdf = if sample.is_delta {
+                None
+            } else {
+                Some(sample.pdf)
+            };
+            ray = ray.spawn(hit.point.clone(), sample.wi);
+
+            if depth + 1 >= integrator.rr_min_depth {
+                let continue_probability = throughput.max_component().min(1.0);
+                if sampler.get_1d() >= continue_probability {
+                    break;
+                }
+                throughput /= continue_probability;
+            }
+        }
+
+        color
+    }
+}
+
+/// The weight given to emission found by a path whose last bounce was sampled with density
+/// `bsdf_pdf`, where `light_pdf` is the density direct lighting samples the same direction with,
+/// or None if direct lighting never samples this emitter. Emitters that are listed but can't be
+/// sampled, whose density is 0, are only found this way, so their emission keeps its full weight.
+fn emission_weight(integrator: &Integrator, bsdf_pdf: Option<f64>, light_pdf: Option<f64>) -> f64 {
+    match (bsdf_pdf, light_pdf) {
+        (Some(bsdf_pdf), Some(light_pdf)) if light_pdf > 0.0 => {
+            if integrator.mis {
+                power_heuristic(bsdf_pdf, light_pdf)
+            } else {
+                0.0
+            }
         }
+        _ => 1.0,
+    }
+}
+
+/// Estimates the light arriving directly from the scene's lights and environment that is
+/// scattered towards `wo`, by picking one of them and tracing a shadow ray towards it, spawned from
+/// `ray`, the ray that found `hit`.
+fn sample_lights(
+    scene: &Scene,
+    integrator: &Integrator,
+    light_sampler: &dyn LightSampler,
+    sampler: &mut dyn Sampler,
+    wo: &Vec3,
+    hit: &HitRecord,
+    ray: &Ray,
+) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+
+    // both dimensions are drawn whichever light is picked, so that every bounce takes the same
+    // dimensions of the sample
+    let u_light = sampler.get_1d();
+    let u = sampler.get_2d();
+
+    let area_count = scene.lights.len();
+    let Some((index, pmf)) = light_sampler.sample(&hit.point, u_light) else {
+        return black;
+    };
+    // `pdf` is the density of the sampled direction, or None for punctual lights, which only have
+    // a probability of being picked
+    let (shadow_ray, radiance, pdf, occluder_t) = if index < area_count {
+        let light = scene.lights[index];
+        let direction = light.random(&hit.point, u).unit();
+        let shadow_ray = ray.spawn(hit.point.clone(), direction);
+        let Some(light_hit) = light.hit(&shadow_ray, Interval::new(0.001, f64::INFINITY)) else {
+            return black;
+        };
+        let pdf = light.pdf_value(&hit.point, &shadow_ray.direction);
+        let occluder_t = Interval::new(0.001, light_hit.t - 0.001);
+        (
+            shadow_ray,
+            light_hit.material.emitted(),
+            Some(pdf),
+            occluder_t,
+        )
+    } else if index < area_count + scene.punctual_lights.len() {
+        let light = scene.punctual_lights[index - area_count];
+        let Some(sample) = light.sample(&hit.point) else {
+            return black;
+        };
+        let shadow_ray = ray.spawn(hit.point.clone(), sample.wi);
+        let occluder_t = Interval::new(0.001, sample.distance - 0.001);
+        (shadow_ray, sample.radiance, None, occluder_t)
+    } else {
+        let Some((direction, pdf)) = scene.environment.sample(u) else {
+            return black;
+        };
+        let radiance = scene.environment.radiance(&direction);
+        let shadow_ray = ray.spawn(hit.point.clone(), direction);
+        let occluder_t = Interval::new(0.001, f64::INFINITY);
+        (shadow_ray, radiance, Some(pdf), occluder_t)
+    };
+
+    let pdf = pdf.map(|pdf| pdf * pmf);
+    if pdf.is_some_and(|pdf| pdf <= 0.0) || radiance.is_near_zero() {
+        return black;
+    }
+    let f = hit.material.eval(wo, &shadow_ray.direction, hit);
+    if f.is_near_zero() {
+        return black; // delta lobes and directions below the surface receive no light
+    }
 
-        let direction_unit = self.direction.unit();
-        // `a` is a value in the range [0,1] based on the direction_unit's y component
-        let a = 0.5 * (direction_unit.y() + 1.0);
-        lerp(a, &white, &blue)
+    let transmittance = transmittance(scene.hittables, &shadow_ray, occluder_t);
+    if transmittance <= 0.0 {
+        return black;
     }
+
+    let Some(pdf) = pdf else {
+        // paths can never hit a punctual light, so there's nothing to weight against
+        return f * radiance * (transmittance / pmf);
+    };
+    let weight = if integrator.mis {
+        let bsdf_pdf = hit.material.pdf(wo, &shadow_ray.direction, hit);
+        power_heuristic(pdf, bsdf_pdf)
+    } else {
+        1.0
+    };
+    f * radiance * (transmittance * weight / pdf)
 }
 
-/// Linear blend/linear interpolation/lerp
-/// Takes some value `a` in the range `[0,1]` and start & end values. Calculates the value that
-/// pertains to `a` when placed in the gradient between start & end.
-fn lerp(a: f64, start_value: &Color, end_value: &Color) -> Color {
-    start_value * (1.0 - a) + end_value * a
+/// Veach's power heuristic (with an exponent of 2), weighting a sample drawn with density `pdf`
+/// against the other strategy that could have drawn it with density `other_pdf`.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let pdf_sq = pdf * pdf;
+    let other_pdf_sq = other_pdf * other_pdf;
+    if pdf_sq + other_pdf_sq == 0.0 {
+        return 0.0;
+    }
+    pdf_sq / (pdf_sq + other_pdf_sq)
 }
 
-fn hit_any<'a>(
-    hittables: &'a [&dyn Hittable],
+/// The closest hit along the ray within `ray_t`, along with the hittable that was hit.
+pub fn hit_any<'a>(
+    hittables: &'a [&'a dyn Hittable],
     ray: &Ray,
     ray_t: Interval,
-) -> Option<HitRecord<'a>> {
+) -> Option<(HitRecord<'a>, &'a dyn Hittable)> {
     let mut curr_closest = ray_t.max;
-    let mut hit_record: Option<HitRecord> = None;
+    let mut hit_record: Option<(HitRecord, &dyn Hittable)> = None;
 
     for h in hittables {
         let hit_option = h.hit(ray, Interval::new(ray_t.min, curr_closest));
         if let Some(hit) = hit_option {
             curr_closest = hit.t;
-            hit_record = Some(hit)
+            hit_record = Some((hit, *h))
         }
     }
     hit_record
 }
+
+/// The fraction of light that makes it along the ray through all of the hittables within `ray_t`.
+fn transmittance(hittables: &[&dyn Hittable], ray: &Ray, ray_t: Interval) -> f64 {
+    let mut transmittance = 1.0;
+    for h in hittables {
+        transmittance *= h.transmittance(ray, Interval::new(ray_t.min, ray_t.max));
+        if transmittance <= 0.0 {
+            break;
+        }
+    }
+    transmittance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        environments::gradient::Gradient,
+        integrator::LightSelection,
+        materials::{diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal},
+        quad::Quad,
+        samplers::independent::Independent,
+        sphere::Sphere,
+    };
+
+    #[test]
+    fn test_rng_depends_on_sample_and_stream() {
+        let mut ray = Ray::new(Point::new(0.0, 1.0, 2.0), Vec3::new(0.0, 0.0, -1.0), 0.5);
+        let first = ray.rng(1.0, 0).next_u32();
+
+        assert_eq!(first, ray.rng(1.0, 0).next_u32());
+        assert_ne!(first, ray.rng(1.0, 1).next_u32());
+        assert_ne!(first, ray.rng(2.0, 0).next_u32());
+        ray.sample_seed = 1;
+        assert_ne!(first, ray.rng(1.0, 0).next_u32());
+        // rays spawned along the same path carry the sample on
+        let spawned = ray.spawn(ray.origin.clone(), ray.direction.clone());
+        assert_eq!(ray.rng(1.0, 0).next_u32(), spawned.rng(1.0, 0).next_u32());
+    }
+
+    #[test]
+    fn test_power_heuristic() {
+        assert_eq!(0.8, power_heuristic(2.0, 1.0));
+        assert_eq!(0.2, power_heuristic(1.0, 2.0));
+        assert_eq!(1.0, power_heuristic(3.0, 0.0));
+        assert_eq!(0.0, power_heuristic(0.0, 0.0));
+        // the weights of the two strategies for the same direction sum to 1
+        let (a, b) = (0.37, 5.2);
+        assert!((power_heuristic(a, b) + power_heuristic(b, a) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_mis_matches_bsdf_and_light_sampling() {
+        let floor_material = Metal::microfacet(Color::new(0.8, 0.8, 0.8), 0.5);
+        let light = DiffuseLight::new(Color::new(4.0, 4.0, 4.0));
+        let floor = Quad::new(
+            Point::new(-50.0, 0.0, -50.0),
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 100.0),
+            &floor_material,
+        );
+        let lamp = Quad::new(
+            Point::new(-1.0, 2.0, -1.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            &light,
+        );
+        let hittables: [&dyn Hittable; 2] = [&floor, &lamp];
+        let lights: [&dyn Hittable; 1] = [&lamp];
+        let black = Gradient::new(Color::new(0.0, 0.0, 0.0), Color::new(0.0, 0.0, 0.0));
+        let ray = Ray::new(Point::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0), 0.0);
+
+        let estimate = |lights: &[&dyn Hittable], mis: bool| {
+            let scene = Scene::new(&hittables, lights, &black);
+            let integrator = Integrator {
+                mis,
+                light_selection: LightSelection::Uniform,
+                ..Integrator::default()
+            };
+            let light_sampler = integrator.light_sampler(&scene);
+            let mut sampler = Independent::new(1, 3);
+            let n = 200_000;
+            let mut sum = 0.0;
+            for i in 0..n {
+                sampler.start_pixel_sample([0, 0], i);
+                sum += ray
+                    .color(&scene, &integrator, light_sampler.as_ref(), &mut sampler)
+                    .y();
+            }
+            sum / n as f64
+        };
+        // with no lights listed the lamp is only found by BSDF sampling, and without MIS only by
+        // light sampling
+        let bsdf_only = estimate(&[], true);
+        let light_only = estimate(&lights, false);
+        let mis = estimate(&lights, true);
+
+        assert!(mis > 0.1);
+        assert!((mis - bsdf_only).abs() < 0.02 * mis, "{mis} vs {bsdf_only}");
+        assert!(
+            (mis - light_only).abs() < 0.02 * mis,
+            "{mis} vs {light_only}"
+        );
+    }
+
+    #[test]
+    fn test_mis_off_keeps_emitters_that_cant_be_sampled() {
+        let grey = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let light = DiffuseLight::new(Color::new(4.0, 4.0, 4.0));
+        let floor = Quad::new(
+            Point::new(-50.0, 0.0, -50.0),
+            Vec3::new(100.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 100.0),
+            &grey,
+        );
+        // a moving sphere can't be sampled as a light, so it's only found by BSDF sampling even
+        // when it's listed
+        let lamp = Sphere::new_moving(
+            Point::new(0.0, 2.0, 0.0),
+            Point::new(0.2, 2.0, 0.0),
+            0.0,
+            1.0,
+            1.0,
+            &light,
+        );
+        let hittables: [&dyn Hittable; 2] = [&floor, &lamp];
+        let lights: [&dyn Hittable; 1] = [&lamp];
+        let black = Gradient::new(Color::new(0.0, 0.0, 0.0), Color::new(0.0, 0.0, 0.0));
+        let ray = Ray::new(Point::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0), 0.5);
+
+        let estimate = |lights: &[&dyn Hittable], mis: bool| {
+            let scene = Scene::new(&hittables, lights, &black);
+            let integrator = Integrator {
+                mis,
+                ..Integrator::default()
+            };
+            let light_sampler = integrator.light_sampler(&scene);
+            let mut sampler = Independent::new(1, 5);
+            let n = 100_000;
+            let mut sum = 0.0;
+            for i in 0..n {
+                sampler.start_pixel_sample([0, 0], i);
+                sum += ray
+                    .color(&scene, &integrator, light_sampler.as_ref(), &mut sampler)
+                    .y();
+            }
+            sum / n as f64
+        };
+        let unlisted = estimate(&[], false);
+        let listed = estimate(&lights, false);
+
+        let with_mis = estimate(&lights, true);
+
+        assert!(unlisted > 0.1);
+        assert!(
+            (listed - unlisted).abs() < 0.03 * unlisted,
+            "{listed} vs {unlisted}"
+        );
+        assert!(
+            (with_mis - unlisted).abs() < 0.03 * unlisted,
+            "{with_mis} vs {unlisted}"
+        );
+    }
+}
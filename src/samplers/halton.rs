@@ -0,0 +1,64 @@
+use crate::{description::Description, pcg::hash};
+
+use super::{
+    low_discrepancy::{owen_scrambled_radical_inverse, PRIMES},
+    sampler::{pixel_key, Sampler},
+};
+
+/// The Halton sequence, whose `d`th dimension is the radical inverse of the sample index in the
+/// `d`th prime. Each pixel gets its own Owen scrambling of the sequence, which breaks up the
+/// correlations between pixels and between the higher dimensions. Dimensions past the table of
+/// primes wrap around to the start of it, scrambled differently.
+pub struct Halton {
+    samples_per_pixel: usize,
+    seed: u64,
+    pixel: [i32; 2],
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl Halton {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        Halton {
+            samples_per_pixel,
+            seed,
+            pixel: [0, 0],
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for Halton {
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn start_pixel_sample(&mut self, pixel: [i32; 2], sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let key = hash(&[pixel_key(self.pixel), self.dimension, self.seed]);
+        let base = PRIMES[self.dimension as usize % PRIMES.len()];
+        self.dimension += 1;
+        owen_scrambled_radical_inverse(base, self.sample_index as u64, key as u32)
+    }
+
+    fn get_2d(&mut self) -> [f64; 2] {
+        [self.get_1d(), self.get_1d()]
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("halton")
+            .u64(self.samples_per_pixel as u64)
+            .u64(self.seed);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{
+    description::Description,
+    pcg::{hash, Pcg32},
+};
+
+use super::sampler::{pixel_key, Sampler};
+
+/// Uniform random numbers with no attempt to spread them out, as a baseline.
+pub struct Independent {
+    samples_per_pixel: usize,
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl Independent {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        Independent {
+            samples_per_pixel,
+            seed,
+            rng: Pcg32::default(),
+        }
+    }
+}
+
+impl Sampler for Independent {
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn start_pixel_sample(&mut self, pixel: [i32; 2], sample_index: usize) {
+        // each pixel gets its own stream, and each sample its own stretch of it
+        self.rng = Pcg32::new(hash(&[pixel_key(pixel), self.seed]), self.seed);
+        self.rng.advance(sample_index as u64 * 65536);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        self.rng.next_f64()
+    }
+
+    fn get_2d(&mut self) -> [f64; 2] {
+        [self.rng.next_f64(), self.rng.next_f64()]
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("independent")
+            .u64(self.samples_per_pixel as u64)
+            .u64(self.seed);
+    }
+}
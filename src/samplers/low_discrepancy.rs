@@ -0,0 +1,153 @@
+//! Building blocks for low discrepancy sequences and their randomisation, after pbrt-v4.
+
+use crate::pcg::mix_bits;
+
+/// The bases of the Halton sequence's dimensions.
+pub const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131,
+];
+
+/// The largest f64 below 1.
+const ONE_MINUS_EPSILON: f64 = 1.0 - f64::EPSILON / 2.0;
+
+/// Reflects the digits of `i` in `base` about the radix point, giving the van der Corput
+/// sequence in that base.
+pub fn radical_inverse(base: u64, mut i: u64) -> f64 {
+    let inverse_base = 1.0 / base as f64;
+    let mut inverse_base_n = 1.0;
+    let mut reversed = 0_u64;
+    while i > 0 {
+        reversed = reversed * base + i % base;
+        inverse_base_n *= inverse_base;
+        i /= base;
+    }
+    (reversed as f64 * inverse_base_n).min(ONE_MINUS_EPSILON)
+}
+
+/// The radical inverse of `i` with its digits Owen scrambled: each digit is permuted by a
+/// permutation chosen by `seed` and all of the digits before it, which keeps the points'
+/// stratification while decorrelating them.
+pub fn owen_scrambled_radical_inverse(base: u64, mut i: u64, seed: u32) -> f64 {
+    // every digit that fits in a u64 is scrambled, since zeros past the last digit of `i` can be
+    // permuted into something else
+    let digits = (63.0 / (base as f64).log2()).floor() as usize;
+    let inverse_base = 1.0 / base as f64;
+    let mut inverse_base_n = 1.0;
+    let mut reversed = 0_u64;
+    for _ in 0..digits {
+        let digit = i % base;
+        i /= base;
+        let digit_hash = mix_bits(seed as u64 ^ reversed) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_hash) as u64;
+        reversed = reversed * base + digit;
+        inverse_base_n *= inverse_base;
+    }
+    (reversed as f64 * inverse_base_n).min(ONE_MINUS_EPSILON)
+}
+
+/// The `i`th element of a random permutation of `0..length` chosen by `seed`, without building
+/// the permutation, from Kensler's "Correlated Multi-Jittered Sampling".
+pub fn permutation_element(mut i: u32, length: u32, seed: u32) -> u32 {
+    let length = length.max(1);
+    let mut w = length - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    let p = seed;
+    loop {
+        i ^= p;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= p >> 16;
+        i ^= (i & w) >> 4;
+        i ^= p >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= p >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | p >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    (i.wrapping_add(p)) % length
+}
+
+/// The `i`th point in `dimension` (0 or 1) of the Sobol sequence, Owen scrambled by `seed`.
+pub fn sobol_sample(i: u32, dimension: usize, seed: u32) -> f64 {
+    let mut v = 0_u32;
+    let mut column = 1_u32; // the second dimension's direction numbers, built up as we go
+    for bit in 0..32 {
+        if i >> bit == 0 {
+            break;
+        }
+        if (i >> bit) & 1 == 1 {
+            v ^= match dimension {
+                0 => 1 << (31 - bit),
+                _ => column << (31 - bit),
+            };
+        }
+        column ^= column << 1;
+    }
+    (fast_owen_scramble(v, seed) as f64 * (1.0 / 4294967296.0)).min(ONE_MINUS_EPSILON)
+}
+
+/// An approximation of Owen scrambling the bits of `v` by `seed`, from Burley's "Practical
+/// Hash-based Owen Scrambling".
+fn fast_owen_scramble(v: u32, seed: u32) -> u32 {
+    let mut v = v.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    v.reverse_bits()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sobol_stratification() {
+        // the first 2^k points of the first two dimensions put one point in each cell of every
+        // grid of 2^k cells, scrambled or not
+        for seed in [0, 12345] {
+            for (columns, rows) in [(16, 1), (4, 4), (2, 8), (1, 16)] {
+                let mut counts = [0; 16];
+                for i in 0..16 {
+                    let x = sobol_sample(i, 0, seed);
+                    let y = sobol_sample(i, 1, seed.wrapping_mul(31) + 7);
+                    let cell = (y * rows as f64) as usize * columns + (x * columns as f64) as usize;
+                    counts[cell] += 1;
+                }
+                assert!(
+                    counts.iter().all(|c| *c == 1),
+                    "{}x{}: {:?}",
+                    columns,
+                    rows,
+                    counts
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_permutation_element() {
+        let mut seen = [false; 10];
+        for i in 0..10 {
+            seen[permutation_element(i, 10, 0xdeadbeef) as usize] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod halton;
+pub mod independent;
+pub mod low_discrepancy;
+pub mod sampler;
+pub mod sobol;
+pub mod stratified;
@@ -0,0 +1,33 @@
+use crate::description::Description;
+
+/// The source of the random numbers each sample of a pixel is built from. Every sample is a point
+/// in as many dimensions as it needs, handed out one or two at a time in the order they're asked
+/// for, and samplers can spread those points more evenly than independent random numbers would,
+/// so that images converge in fewer samples.
+///
+/// A sample depends only on the sampler's seed, the pixel and the sample's index, however many
+/// samples were taken before it.
+pub trait Sampler {
+    /// The number of samples to take in each pixel.
+    fn samples_per_pixel(&self) -> usize;
+
+    /// The seed every sample is derived from.
+    fn seed(&self) -> u64;
+
+    /// Starts the `sample_index`th sample of `pixel`, from its first dimension.
+    fn start_pixel_sample(&mut self, pixel: [i32; 2], sample_index: usize);
+
+    /// The sample's next dimension, in `[0, 1)`.
+    fn get_1d(&mut self) -> f64;
+
+    /// The sample's next two dimensions, in `[0, 1)`.
+    fn get_2d(&mut self) -> [f64; 2];
+
+    /// Adds the kind of sampler and its settings to `description`.
+    fn describe(&self, description: &mut Description);
+}
+
+/// Packs `pixel` into a single value for hashing.
+pub fn pixel_key(pixel: [i32; 2]) -> u64 {
+    ((pixel[0] as u32 as u64) << 32) | pixel[1] as u32 as u64
+}
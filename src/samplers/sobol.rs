@@ -0,0 +1,78 @@
+use crate::{description::Description, pcg::hash};
+
+use super::{
+    low_discrepancy::{permutation_element, sobol_sample},
+    sampler::{pixel_key, Sampler},
+};
+
+/// Owen scrambled Sobol points, padded: each dimension, or pair of dimensions, takes the first
+/// two dimensions of the Sobol sequence, which are well stratified in 2D, with the order of the
+/// points shuffled so that the pairs aren't correlated with each other. Converges best with a
+/// power of two samples per pixel.
+pub struct Sobol {
+    samples_per_pixel: usize,
+    seed: u64,
+    pixel: [i32; 2],
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl Sobol {
+    pub fn new(samples_per_pixel: usize, seed: u64) -> Self {
+        Sobol {
+            samples_per_pixel,
+            seed,
+            pixel: [0, 0],
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// The hash scrambling the current dimension, and this sample's shuffled index in it.
+    fn next_index(&mut self, dimensions: u64) -> (u64, u32) {
+        let key = hash(&[pixel_key(self.pixel), self.dimension, self.seed]);
+        self.dimension += dimensions;
+        let index = permutation_element(
+            self.sample_index as u32,
+            self.samples_per_pixel as u32,
+            key as u32,
+        );
+        (key, index)
+    }
+}
+
+impl Sampler for Sobol {
+    fn samples_per_pixel(&self) -> usize {
+        self.samples_per_pixel
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn start_pixel_sample(&mut self, pixel: [i32; 2], sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let (key, index) = self.next_index(1);
+        sobol_sample(index, 0, (key >> 32) as u32)
+    }
+
+    fn get_2d(&mut self) -> [f64; 2] {
+        let (key, index) = self.next_index(2);
+        [
+            sobol_sample(index, 0, key as u32),
+            sobol_sample(index, 1, (key >> 32) as u32),
+        ]
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("sobol")
+            .u64(self.samples_per_pixel as u64)
+            .u64(self.seed);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    description::Description,
+    pcg::{hash, Pcg32},
+};
+
+use super::{
+    low_discrepancy::permutation_element,
+    sampler::{pixel_key, Sampler},
+};
+
+/// Divides each dimension (or pair of dimensions) into as many strata as there are samples, and
+/// puts one sample in each, jittered within it. Which stratum each sample gets is shuffled
+/// separately for every dimension, so that the dimensions aren't correlated.
+pub struct Stratified {
+    x_strata: usize,
+    y_strata: usize,
+    /// Places samples at random within their strata rather than at their centres.
+    jitter: bool,
+    seed: u64,
+    rng: Pcg32,
+    pixel: [i32; 2],
+    sample_index: usize,
+    dimension: u64,
+}
+
+impl Stratified {
+    /// Takes `x_strata * y_strata` samples per pixel, arranged in that grid in 2D.
+    pub fn new(x_strata: usize, y_strata: usize, jitter: bool, seed: u64) -> Self {
+        Stratified {
+            x_strata: x_strata.max(1),
+            y_strata: y_strata.max(1),
+            jitter,
+            seed,
+            rng: Pcg32::default(),
+            pixel: [0, 0],
+            sample_index: 0,
+            dimension: 0,
+        }
+    }
+
+    /// The stratum this sample takes in the current dimension, which moves on to the next.
+    fn next_stratum(&mut self, dimensions: u64) -> usize {
+        let key = hash(&[pixel_key(self.pixel), self.dimension, self.seed]);
+        self.dimension += dimensions;
+        permutation_element(
+            self.sample_index as u32,
+            self.samples_per_pixel() as u32,
+            key as u32,
+        ) as usize
+    }
+
+    fn offset(&mut self) -> f64 {
+        if self.jitter {
+            self.rng.next_f64()
+        } else {
+            0.5
+        }
+    }
+}
+
+impl Sampler for Stratified {
+    fn samples_per_pixel(&self) -> usize {
+        self.x_strata * self.y_strata
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn start_pixel_sample(&mut self, pixel: [i32; 2], sample_index: usize) {
+        self.pixel = pixel;
+        self.sample_index = sample_index;
+        self.dimension = 0;
+        self.rng = Pcg32::new(hash(&[pixel_key(pixel), self.seed]), self.seed);
+        self.rng.advance(sample_index as u64 * 65536);
+    }
+
+    fn get_1d(&mut self) -> f64 {
+        let stratum = self.next_stratum(1);
+        (stratum as f64 + self.offset()) / self.samples_per_pixel() as f64
+    }
+
+    fn get_2d(&mut self) -> [f64; 2] {
+        let stratum = self.next_stratum(2);
+        let (x, y) = (stratum % self.x_strata, stratum / self.x_strata);
+        [
+            (x as f64 + self.offset()) / self.x_strata as f64,
+            (y as f64 + self.offset()) / self.y_strata as f64,
+        ]
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("stratified")
+            .u64(self.x_strata as u64)
+            .u64(self.y_strata as u64)
+            .u64(self.jitter as u64)
+            .u64(self.seed);
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use crate::{
+    description::Description, environments::environment::Environment, hittable::Hittable,
+    lights::light::Light,
+};
+
+/// Everything that can be rendered: the hittables rays are traced against, the subset of them
+/// that emit light and should be sampled explicitly, any punctual lights, and the environment
+/// lighting rays that escape.
+pub struct Scene<'a> {
+    pub hittables: &'a [&'a dyn Hittable],
+    /// Emissive hittables to sample for direct lighting. Emitters that aren't listed still light
+    /// the scene, but only through BSDF sampling, when a path happens to hit them, so small or
+    /// distant ones are noisy. `Scene::new` indexes them, so they should be given there rather
+    /// than replaced afterwards.
+    pub lights: &'a [&'a dyn Hittable],
+    /// Lights that can't be hit, only sampled. Empty unless set with `with_punctual_lights`.
+    pub punctual_lights: &'a [&'a dyn Light],
+    /// Sampled for direct lighting alongside the lights, if it supports sampling.
+    pub environment: &'a dyn Environment,
+    /// The index of each of `lights` by its address, so that emitters found by paths can be
+    /// looked up without searching the lights.
+    light_indices: HashMap<usize, usize>,
+}
+
+impl<'a> Scene<'a> {
+    pub fn new(
+        hittables: &'a [&'a dyn Hittable],
+        lights: &'a [&'a dyn Hittable],
+        environment: &'a dyn Environment,
+    ) -> Self {
+        let light_indices = lights
+            .iter()
+            .enumerate()
+            .map(|(index, light)| (address(*light), index))
+            .collect();
+        Scene {
+            hittables,
+            lights,
+            punctual_lights: &[],
+            environment,
+            light_indices,
+        }
+    }
+
+    /// The scene with `punctual_lights` sampled alongside its other lights.
+    pub fn with_punctual_lights(self, punctual_lights: &'a [&'a dyn Light]) -> Self {
+        Scene {
+            punctual_lights,
+            ..self
+        }
+    }
+
+    /// The index light samplers know `hittable` by, if it is one of the scene's lights.
+    pub fn light_index(&self, hittable: &dyn Hittable) -> Option<usize> {
+        let index = *self.light_indices.get(&address(hittable))?;
+        // emitters found through a replaced `lights` aren't recognised, rather than misidentified
+        self.lights
+            .get(index)
+            .filter(|light| std::ptr::addr_eq(**light, hittable))
+            .map(|_| index)
+    }
+
+    /// The number of light sources direct lighting picks between, counting a sampled environment.
+    pub fn light_count(&self) -> usize {
+        self.lights.len() + self.punctual_lights.len() + self.environment.can_sample() as usize
+    }
+
+    /// Adds everything in the scene to `description`. Lights are described again, rather than
+    /// by where they are in `hittables`, as they needn't be there.
+    pub fn describe(&self, description: &mut Description) {
+        description.u64(self.hittables.len() as u64);
+        for hittable in self.hittables {
+            hittable.describe(description);
+        }
+        description.u64(self.lights.len() as u64);
+        for light in self.lights {
+            light.describe(description);
+        }
+        description.u64(self.punctual_lights.len() as u64);
+        for light in self.punctual_lights {
+            light.describe(description);
+        }
+        self.environment.describe(description);
+    }
+}
+
+fn address(hittable: &dyn Hittable) -> usize {
+    (hittable as *const dyn Hittable).cast::<()>() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color, environments::gradient::Gradient, materials::diffuse_light::DiffuseLight,
+        point::Point, sphere::Sphere,
+    };
+
+    #[test]
+    fn test_light_index() {
+        let light = DiffuseLight::new(Color::new(1.0, 1.0, 1.0));
+        let spheres: Vec<Sphere> = (0..3)
+            .map(|i| Sphere::new(Point::new(i as f64, 0.0, 0.0), 0.5, &light))
+            .collect();
+        let hittables: [&dyn Hittable; 3] = [&spheres[0], &spheres[1], &spheres[2]];
+        let lights: [&dyn Hittable; 2] = [&spheres[2], &spheres[0]];
+        let sky = Gradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.5, 0.7, 1.0));
+        let scene = Scene::new(&hittables, &lights, &sky);
+
+        assert_eq!(Some(1), scene.light_index(&spheres[0]));
+        assert_eq!(None, scene.light_index(&spheres[1]));
+        assert_eq!(Some(0), scene.light_index(&spheres[2]));
+    }
+}
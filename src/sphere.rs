@@ -1,10 +1,26 @@
+use std::f64::consts::PI;
+
 use crate::{
-    hit_record::HitRecord, hittable::Hittable, interval::Interval, materials::material::Material,
-    point::Point, ray::Ray, vec3::dot,
+    aabb::Aabb,
+    color::luminance,
+    description::Description,
+    hit_record::HitRecord,
+    hittable::Hittable,
+    interval::Interval,
+    materials::material::Material,
+    onb::Onb,
+    point::Point,
+    ray::Ray,
+    vec3::{dot, Vec3},
 };
 
 pub struct Sphere<'a> {
+    /// The centre at `time0`, or at all times if the sphere doesn't move.
     centre: Point,
+    /// The centre at `time1`.
+    centre1: Point,
+    time0: f64,
+    time1: f64,
     radius: f64,
     material: &'a dyn Material,
 }
@@ -12,14 +28,75 @@ pub struct Sphere<'a> {
 impl<'a> Sphere<'a> {
     pub fn new(centre: Point, radius: f64, material: &'a dyn Material) -> Self {
         Sphere {
+            centre1: centre.clone(),
             centre,
+            time0: 0.0,
+            time1: 0.0,
+            radius,
+            material,
+        }
+    }
+
+    /// A sphere moving in a straight line from `centre0` at `time0` to `centre1` at `time1`, and
+    /// resting at either end outside that interval.
+    ///
+    /// Moving spheres can't be sampled as lights, since a light's sampling density doesn't depend
+    /// on time. Paths still pick up their emission when they hit them.
+    pub fn new_moving(
+        centre0: Point,
+        centre1: Point,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: &'a dyn Material,
+    ) -> Self {
+        Sphere {
+            centre: centre0,
+            centre1,
+            time0,
+            time1,
             radius,
             material,
         }
     }
+
+    fn is_moving(&self) -> bool {
+        !(&self.centre1 - &self.centre).is_near_zero()
+    }
+
+    /// The position of the centre at `time`.
+    fn centre_at(&self, time: f64) -> Point {
+        if self.time1 <= self.time0 {
+            return if time < self.time0 {
+                self.centre.clone()
+            } else {
+                self.centre1.clone()
+            };
+        }
+        let t = ((time - self.time0) / (self.time1 - self.time0)).clamp(0.0, 1.0);
+        &self.centre + &((&self.centre1 - &self.centre) * t)
+    }
 }
 
 impl<'a> Hittable for Sphere<'a> {
+    fn bounding_box(&self) -> Aabb {
+        // the box around the sphere at either end of its motion covers everywhere in between
+        let extent = Vec3::new(self.radius, self.radius, self.radius);
+        let box0 = Aabb::from_points(&(&self.centre - &extent), &(&self.centre + &extent));
+        let box1 = Aabb::from_points(&(&self.centre1 - &extent), &(&self.centre1 + &extent));
+        Aabb::enclosing(&box0, &box1)
+    }
+
+    fn power(&self) -> f64 {
+        // a moving sphere can't be sampled as a light, so it shouldn't be picked as one
+        if self.is_moving() {
+            return 0.0;
+        }
+        // a diffuse emitter gives off pi times its radiance per unit area
+        let area = 4.0 * PI * self.radius * self.radius;
+        luminance(&self.material.emitted()) * area * PI
+    }
+
     /**
     The equation for a sphere centered at point `C = (Cx, Cy, Cz)` with radius `r` is:
     `(Cx - Px)^2 + (Cy - Py)^2 + (Cz - Pz)^2 = r^2`
@@ -39,8 +116,9 @@ impl<'a> Hittable for Sphere<'a> {
     Given some ray and some sphere, we'd like to know if any point along that ray is on the surface
     of the sphere.
     */
-    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord> {
-        let oc = &self.centre - &ray.origin;
+    fn hit(&self, ray: &Ray, ray_t: Interval) -> Option<HitRecord<'_>> {
+        let centre = self.centre_at(ray.time);
+        let oc = &centre - &ray.origin;
         let a = ray.direction.len_sq();
         let h = dot(&ray.direction, &oc);
         let c = oc.len_sq() - self.radius * self.radius;
@@ -51,15 +129,19 @@ impl<'a> Hittable for Sphere<'a> {
 
         let discriminant_sqrt = discriminant.sqrt();
 
-        // find the nearest root that lies within the acceptable t range
-        let root = (h - discriminant_sqrt) / a;
+        // find the nearest root that lies within the acceptable t range. the far root is hit by
+        // rays that start inside the sphere, such as those refracted into it
+        let mut root = (h - discriminant_sqrt) / a;
         if !ray_t.surrounds(root) {
-            return None;
+            root = (h + discriminant_sqrt) / a;
+            if !ray_t.surrounds(root) {
+                return None;
+            }
         }
 
         let t = root;
         let point = ray.at(t);
-        let outward_normal_unit = (&point - &self.centre) / self.radius;
+        let outward_normal_unit = (&point - &centre) / self.radius;
         let front_face = dot(&ray.direction, &outward_normal_unit) < 0.0;
         let normal = if front_face {
             outward_normal_unit
@@ -69,4 +151,111 @@ impl<'a> Hittable for Sphere<'a> {
 
         Some(HitRecord::new(point, normal, t, front_face, self.material))
     }
+
+    /// When `origin` is outside the sphere, directions are sampled uniformly within the cone the
+    /// sphere subtends. From the inside, points are sampled uniformly over the sphere's surface.
+    fn pdf_value(&self, origin: &Point, direction: &Vec3) -> f64 {
+        if self.is_moving() {
+            return 0.0;
+        }
+        let ray = Ray::new(origin.clone(), direction.clone(), self.time0);
+        let Some(hit) = self.hit(&ray, Interval::new(0.001, f64::INFINITY)) else {
+            return 0.0;
+        };
+
+        let distance_sq = (&self.centre - origin).len_sq();
+        let radius_sq = self.radius * self.radius;
+        if distance_sq <= radius_sq {
+            // convert the area density to solid angle
+            let hit_distance_sq = hit.t * hit.t * direction.len_sq();
+            let cos_theta = dot(&direction.unit(), &hit.normal).abs();
+            let area = 4.0 * PI * radius_sq;
+            return hit_distance_sq / (cos_theta * area);
+        }
+
+        let cos_theta_max = (1.0 - radius_sq / distance_sq).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        1.0 / solid_angle
+    }
+
+    fn random(&self, origin: &Point, u: [f64; 2]) -> Vec3 {
+        let to_centre = &self.centre - origin;
+        let distance_sq = to_centre.len_sq();
+        let radius_sq = self.radius * self.radius;
+        if distance_sq <= radius_sq {
+            let point = &self.centre + &(Vec3::on_unit_sphere(u) * self.radius);
+            return point - origin.clone();
+        }
+
+        let cos_theta_max = (1.0 - radius_sq / distance_sq).sqrt();
+        let z = 1.0 + u[0] * (cos_theta_max - 1.0);
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * u[1];
+        let onb = Onb::new(&to_centre.unit());
+        onb.to_world(&Vec3::new(r * phi.cos(), r * phi.sin(), z))
+    }
+
+    fn describe(&self, description: &mut Description) {
+        description
+            .kind("sphere")
+            .vec3(&self.centre)
+            .vec3(&self.centre1)
+            .f64(self.time0)
+            .f64(self.time1)
+            .f64(self.radius);
+        self.material.describe(description);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color,
+        materials::{diffuse_light::DiffuseLight, lambertian::Lambertian},
+    };
+
+    #[test]
+    fn test_moving_sphere() {
+        let material = Lambertian::new(Color::new(0.5, 0.5, 0.5));
+        let sphere = Sphere::new_moving(
+            Point::new(0.0, 0.0, -2.0),
+            Point::new(2.0, 0.0, -2.0),
+            1.0,
+            2.0,
+            0.5,
+            &material,
+        );
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let towards =
+            |x: f64, time: f64| Ray::new(Point::new(x, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0), time);
+
+        // resting at the first keyframe before it, halfway along midway through, and resting at
+        // the last keyframe after it
+        assert!(sphere.hit(&towards(0.0, 0.0), ray_t.clone()).is_some());
+        assert!(sphere.hit(&towards(1.0, 0.0), ray_t.clone()).is_none());
+        assert!(sphere.hit(&towards(1.0, 1.5), ray_t.clone()).is_some());
+        assert!(sphere.hit(&towards(2.0, 3.0), ray_t).is_some());
+
+        let bbox = sphere.bounding_box();
+        assert_eq!(-0.5, bbox.x.min);
+        assert_eq!(2.5, bbox.x.max);
+    }
+
+    #[test]
+    fn test_moving_sphere_is_not_a_light() {
+        let light = DiffuseLight::new(Color::new(1.0, 1.0, 1.0));
+        let still = Sphere::new(Point::new(0.0, 0.0, -2.0), 0.5, &light);
+        let moving = Sphere::new_moving(
+            Point::new(0.0, 0.0, -2.0),
+            Point::new(2.0, 0.0, -2.0),
+            1.0,
+            2.0,
+            0.5,
+            &light,
+        );
+
+        assert!(still.power() > 0.0);
+        assert_eq!(0.0, moving.power());
+    }
 }
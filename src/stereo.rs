@@ -0,0 +1,156 @@
+use crate::{
+    description::Description,
+    point::Point,
+    vec3::{cross, Vec3},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// How the two eyes' views share one image.
+#[derive(Debug, Clone, Copy)]
+pub enum StereoLayout {
+    /// The left eye in the left half of the image and the right eye in the right half.
+    SideBySide,
+    /// The left eye in the top half of the image and the right eye in the bottom half.
+    OverUnder,
+}
+
+/// Renders a view for each eye into one image, for viewing in a headset.
+#[derive(Debug, Clone)]
+pub struct Stereo {
+    interpupillary_distance: f64,
+    convergence_distance: f64,
+    layout: StereoLayout,
+    omnidirectional: bool,
+}
+
+impl Stereo {
+    /// Eyes `interpupillary_distance` apart along the camera's horizontal axis, with their views
+    /// sheared so that they line up on the plane `convergence_distance` in front of the camera.
+    /// Things nearer than that appear in front of the screen, and things further away behind it.
+    /// An infinite distance keeps the views parallel.
+    pub fn new(
+        interpupillary_distance: f64,
+        convergence_distance: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        Stereo {
+            interpupillary_distance,
+            convergence_distance,
+            layout,
+            omnidirectional: false,
+        }
+    }
+
+    /// Omni-directional stereo (ODS), for 360° panoramas with an `Equirectangular` projection.
+    /// Rather than staying put, the eyes circle the camera's vertical axis so that they're always
+    /// side by side relative to the direction being looked in, as they would be for a viewer
+    /// turning their head. Views converge at `convergence_distance` along each ray.
+    pub fn omnidirectional(
+        interpupillary_distance: f64,
+        convergence_distance: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        Stereo {
+            omnidirectional: true,
+            ..Stereo::new(interpupillary_distance, convergence_distance, layout)
+        }
+    }
+
+    /// Which eye sees the pixel at (x, y) of an image `width` by `height`, along with the pixel's
+    /// position in that eye's view and the view's size.
+    pub fn split(&self, x: i32, y: i32, width: i32, height: i32) -> (Eye, [i32; 4]) {
+        match self.layout {
+            StereoLayout::SideBySide => {
+                let left_width = width / 2;
+                if x < left_width {
+                    (Eye::Left, [x, y, left_width, height])
+                } else {
+                    (Eye::Right, [x - left_width, y, width - left_width, height])
+                }
+            }
+            StereoLayout::OverUnder => {
+                let top_height = height / 2;
+                if y < top_height {
+                    (Eye::Left, [x, y, width, top_height])
+                } else {
+                    (Eye::Right, [x, y - top_height, width, height - top_height])
+                }
+            }
+        }
+    }
+
+    /// Moves the camera space ray from `origin` along `direction` to start from `eye`.
+    pub fn eye_ray(&self, eye: Eye, origin: &Point, direction: &Vec3) -> (Point, Vec3) {
+        let half_distance = match eye {
+            Eye::Left => -self.interpupillary_distance / 2.0,
+            Eye::Right => self.interpupillary_distance / 2.0,
+        };
+        let offset = if self.omnidirectional {
+            // to the right of the direction, level with the horizon. looking straight up or down
+            // there's no telling which way that is, so the eyes meet
+            let right = cross(direction.clone(), Vec3::new(0.0, 1.0, 0.0));
+            if right.is_near_zero() {
+                return (origin.clone(), direction.clone());
+            }
+            right.unit() * half_distance
+        } else {
+            Vec3::new(half_distance, 0.0, 0.0)
+        };
+
+        let eye_origin = origin + &offset;
+        if self.convergence_distance.is_infinite() {
+            return (eye_origin, direction.clone());
+        }
+        // aim at the point the original ray reaches at the convergence distance
+        let target_distance = if self.omnidirectional {
+            self.convergence_distance / direction.len()
+        } else if direction.z() < 0.0 {
+            self.convergence_distance / -direction.z()
+        } else {
+            return (eye_origin, direction.clone()); // never reaches the convergence plane
+        };
+        let target = origin + &(direction * target_distance);
+        let eye_direction = target - eye_origin.clone();
+        (eye_origin, eye_direction)
+    }
+
+    /// Adds how the eyes are placed and laid out to `description`.
+    pub fn describe(&self, description: &mut Description) {
+        description
+            .f64(self.interpupillary_distance)
+            .f64(self.convergence_distance)
+            .u64(self.layout as u64)
+            .u64(self.omnidirectional as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convergence() {
+        let origin = Point::new(0.0, 0.0, 0.0);
+        let direction = Vec3::new(0.3, -0.2, -1.0);
+        let target = &direction * 5.0;
+
+        // both eyes see the point on the convergence plane along the original ray
+        let stereo = Stereo::new(0.064, 5.0, StereoLayout::SideBySide);
+        for eye in [Eye::Left, Eye::Right] {
+            let (eye_origin, eye_direction) = stereo.eye_ray(eye, &origin, &direction);
+            let to_target = (&target - &eye_origin).unit();
+            assert!((&eye_direction.unit() - &to_target).len() < 1e-12);
+        }
+
+        // omni-directional eyes sit level and side on to the view
+        let ods = Stereo::omnidirectional(0.064, f64::INFINITY, StereoLayout::OverUnder);
+        let looking_left = Vec3::new(-1.0, 0.5, 0.0);
+        let (eye_origin, _) = ods.eye_ray(Eye::Right, &origin, &looking_left);
+        assert!((&eye_origin - &Point::new(0.0, 0.0, -0.032)).len() < 1e-12);
+    }
+}
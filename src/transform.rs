@@ -0,0 +1,137 @@
+use crate::{
+    description::Description,
+    point::Point,
+    quaternion::Quaternion,
+    vec3::{cross, dot, Vec3},
+};
+
+/// An affine transform decomposed into a scale, followed by a rotation, followed by a
+/// translation. Keeping the parts separate lets two transforms be interpolated without the
+/// shrinking and skewing that blending their matrices would cause.
+#[derive(Debug, Clone)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quaternion,
+    /// The scale along each of the local axes. Negative factors mirror.
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub fn new(translation: Vec3, rotation: Quaternion, scale: Vec3) -> Self {
+        Transform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn identity() -> Self {
+        Transform::new(
+            Vec3::new(0.0, 0.0, 0.0),
+            Quaternion::identity(),
+            Vec3::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    /// Decomposes the affine transform whose top three rows are `rows`. The linear part is split
+    /// into a rotation and a scale by Gram-Schmidt orthogonalisation of its columns; any shear is
+    /// discarded, and a reflection is folded into the last axis's scale.
+    pub fn from_matrix(rows: [[f64; 4]; 3]) -> Self {
+        let column = |c: usize| Vec3::new(rows[0][c], rows[1][c], rows[2][c]);
+        let (c0, c1, c2) = (column(0), column(1), column(2));
+
+        let sx = c0.len();
+        let r0 = &c0 / sx;
+        let c1 = &c1 - &(&r0 * dot(&c1, &r0));
+        let sy = c1.len();
+        let r1 = &c1 / sy;
+        let c2 = &c2 - &(&r0 * dot(&c2, &r0)) - (&r1 * dot(&c2, &r1));
+        let r2 = cross(r0.clone(), r1.clone());
+        let sz = c2.len().copysign(dot(&c2, &r2));
+
+        Transform {
+            translation: column(3),
+            rotation: Quaternion::from_rotation_matrix(&[r0, r1, r2]),
+            scale: Vec3::new(sx, sy, sz),
+        }
+    }
+
+    /// Blends from `a` at `t = 0` to `b` at `t = 1`, interpolating the translation and scale
+    /// linearly and the rotation spherically.
+    pub fn interpolate(a: &Transform, b: &Transform, t: f64) -> Self {
+        let lerp = |a: &Vec3, b: &Vec3| a * (1.0 - t) + b * t;
+        Transform {
+            translation: lerp(&a.translation, &b.translation),
+            rotation: Quaternion::slerp(&a.rotation, &b.rotation, t),
+            scale: lerp(&a.scale, &b.scale),
+        }
+    }
+
+    pub fn point_to_world(&self, p: &Point) -> Point {
+        &self.translation + &self.vector_to_world(p)
+    }
+
+    pub fn point_to_local(&self, p: &Point) -> Point {
+        self.vector_to_local(&(p - &self.translation))
+    }
+
+    pub fn vector_to_world(&self, v: &Vec3) -> Vec3 {
+        self.rotation.rotate(&(v * &self.scale))
+    }
+
+    pub fn vector_to_local(&self, v: &Vec3) -> Vec3 {
+        let v = self.rotation.inverse().rotate(v);
+        Vec3::new(
+            v.x() / self.scale.x(),
+            v.y() / self.scale.y(),
+            v.z() / self.scale.z(),
+        )
+    }
+
+    /// Transforms the surface normal `n` by the inverse transpose, so it stays perpendicular to
+    /// the transformed surface. The result isn't normalised.
+    pub fn normal_to_world(&self, n: &Vec3) -> Vec3 {
+        let n = Vec3::new(
+            n.x() / self.scale.x(),
+            n.y() / self.scale.y(),
+            n.z() / self.scale.z(),
+        );
+        self.rotation.rotate(&n)
+    }
+
+    /// Adds the transform's parts to `description`.
+    pub fn describe(&self, description: &mut Description) {
+        description
+            .vec3(&self.translation)
+            .f64(self.rotation.w)
+            .vec3(&self.rotation.v)
+            .vec3(&self.scale);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_matrix() {
+        let transform = Transform::new(
+            Vec3::new(1.0, -2.0, 3.0),
+            Quaternion::from_axis_angle(&Vec3::new(0.3, 1.0, -0.2), 75.0),
+            Vec3::new(2.0, 0.5, -1.5),
+        );
+        let origin = transform.point_to_world(&Point::new(0.0, 0.0, 0.0));
+        let columns = [
+            transform.vector_to_world(&Vec3::new(1.0, 0.0, 0.0)),
+            transform.vector_to_world(&Vec3::new(0.0, 1.0, 0.0)),
+            transform.vector_to_world(&Vec3::new(0.0, 0.0, 1.0)),
+        ];
+        let rows = [0, 1, 2].map(|r| [columns[0][r], columns[1][r], columns[2][r], origin[r]]);
+        let res = Transform::from_matrix(rows);
+
+        let p = Point::new(0.7, 1.1, -0.4);
+        let expected = transform.point_to_world(&p);
+        assert!((&res.point_to_world(&p) - &expected).len() < 1e-9);
+        assert!((&res.point_to_local(&expected) - &p).len() < 1e-9);
+    }
+}
@@ -0,0 +1,75 @@
+/// Keeps the running mean and variance of a stream of values with Welford's algorithm, which
+/// stays accurate however many values there are and however large they are relative to their
+/// spread.
+#[derive(Debug, Clone, Default)]
+pub struct VarianceEstimator {
+    count: usize,
+    mean: f64,
+    /// The sum of squared differences from the mean.
+    m2: f64,
+}
+
+impl VarianceEstimator {
+    pub fn new() -> Self {
+        VarianceEstimator::default()
+    }
+
+    /// An estimator that carries on from the state `parts` returned.
+    pub fn from_parts(count: usize, mean: f64, m2: f64) -> Self {
+        VarianceEstimator { count, mean, m2 }
+    }
+
+    /// The estimator's state: the count, the mean and the sum of squared differences from it.
+    pub fn parts(&self) -> (usize, f64, f64) {
+        (self.count, self.mean, self.m2)
+    }
+
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// The sample variance of the values, or 0 until there are two of them.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        self.m2 / (self.count - 1) as f64
+    }
+
+    /// The half-width of the 95% confidence interval for the mean, relative to the mean itself.
+    /// Infinite for a mean of 0 unless the values don't vary at all.
+    pub fn relative_error(&self) -> f64 {
+        let error = 1.96 * (self.variance() / self.count.max(1) as f64).sqrt();
+        if error == 0.0 {
+            return 0.0;
+        }
+        error / self.mean.abs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variance() {
+        let mut estimator = VarianceEstimator::new();
+        for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            estimator.add(value + 1e9);
+        }
+        assert_eq!(estimator.count(), 8);
+        assert!((estimator.mean() - (5.0 + 1e9)).abs() < 1e-6);
+        assert!((estimator.variance() - 32.0 / 7.0).abs() < 1e-6);
+    }
+}
@@ -1,8 +1,4 @@
-use rand::Rng;
-
-use crate::interval::Interval;
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Vec3 {
     v: [f64; 3],
 }
@@ -12,33 +8,34 @@ impl Vec3 {
         Vec3 { v: [x, y, z] }
     }
 
-    pub fn random_on_hemisphere(normal: &Vec3) -> Self {
-        let unit = Vec3::random_unit();
-        if dot(&unit, &normal) > 0.0 {
+    /// Maps a pair of uniform random numbers in `[0, 1)` onto a uniformly distributed direction in
+    /// the hemisphere around `normal`.
+    pub fn random_on_hemisphere(normal: &Vec3, u: [f64; 2]) -> Self {
+        let unit = Vec3::on_unit_sphere(u);
+        if dot(&unit, normal) > 0.0 {
             return unit; // exists in the same hemisphere as the normal
         }
-        return -unit; // flip it if not
-    }
-
-    pub fn random_unit() -> Self {
-        Vec3::random_in_unit_sphere().unit()
+        -unit // flip it if not
     }
 
-    fn random_clamped(interval: &Interval) -> Self {
-        let mut rng = rand::thread_rng();
-        let x = interval.clamp(rng.gen());
-        let y = interval.clamp(rng.gen());
-        let z = interval.clamp(rng.gen());
-        Vec3 { v: [x, y, z] }
+    /// Maps a pair of uniform random numbers in `[0, 1)` onto a uniformly distributed point on the
+    /// unit sphere.
+    pub fn on_unit_sphere(u: [f64; 2]) -> Self {
+        let z = 1.0 - 2.0 * u[0];
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * std::f64::consts::PI * u[1];
+        Vec3 {
+            v: [r * phi.cos(), r * phi.sin(), z],
+        }
     }
 
-    fn random_in_unit_sphere() -> Self {
-        let min_max = Interval::new(-1.0, 1.0);
-        loop {
-            let p = Vec3::random_clamped(&min_max);
-            if p.len_sq() < 1.0 {
-                return p;
-            }
+    /// Maps a pair of uniform random numbers in `[0, 1)` onto a direction in the hemisphere around
+    /// +z, distributed with density `cos(theta) / pi`.
+    pub fn random_cosine_direction(u: [f64; 2]) -> Self {
+        let r = u[0].sqrt();
+        let phi = 2.0 * std::f64::consts::PI * u[1];
+        Vec3 {
+            v: [r * phi.cos(), r * phi.sin(), (1.0 - u[0]).max(0.0).sqrt()],
         }
     }
 
@@ -70,6 +67,10 @@ impl Vec3 {
         }
     }
 
+    pub fn max_component(&self) -> f64 {
+        self.v[0].max(self.v[1]).max(self.v[2])
+    }
+
     /// Returns true if the Vec3 is close to zero in all dimensions
     pub fn is_near_zero(&self) -> bool {
         let s = 0.00000001;
@@ -77,7 +78,7 @@ impl Vec3 {
     }
 
     pub fn reflect(&self, normal: &Vec3) -> Vec3 {
-        let projection = normal * dot(self, &normal);
+        let projection = normal * dot(self, normal);
         let bb = projection * 2.0;
         self - &bb
     }
@@ -108,7 +109,7 @@ impl std::ops::Sub for Vec3 {
     }
 }
 
-impl<'a, 'b> std::ops::Sub<&'b Vec3> for &'a Vec3 {
+impl<'b> std::ops::Sub<&'b Vec3> for &Vec3 {
     type Output = Vec3;
     fn sub(self, other: &'b Vec3) -> Vec3 {
         Vec3 {
@@ -151,7 +152,7 @@ impl std::ops::Add for Vec3 {
     }
 }
 
-impl<'a, 'b> std::ops::Add<&'b Vec3> for &'a Vec3 {
+impl<'b> std::ops::Add<&'b Vec3> for &Vec3 {
     type Output = Vec3;
     fn add(self, other: &'b Vec3) -> Vec3 {
         Vec3 {
@@ -182,7 +183,7 @@ impl std::ops::Mul<f64> for Vec3 {
 }
 
 // Mul for Vec3 reference
-impl<'a> std::ops::Mul<f64> for &'a Vec3 {
+impl std::ops::Mul<f64> for &Vec3 {
     type Output = Vec3;
     fn mul(self, scalar: f64) -> Vec3 {
         Vec3 {
@@ -212,6 +213,19 @@ impl std::ops::Mul<Vec3> for Vec3 {
     }
 }
 
+impl<'b> std::ops::Mul<&'b Vec3> for &Vec3 {
+    type Output = Vec3;
+    fn mul(self, other: &'b Vec3) -> Vec3 {
+        Vec3 {
+            v: [
+                self.v[0] * other.v[0],
+                self.v[1] * other.v[1],
+                self.v[2] * other.v[2],
+            ],
+        }
+    }
+}
+
 impl std::ops::Div<f64> for Vec3 {
     type Output = Vec3;
     fn div(self, other: f64) -> Vec3 {
@@ -228,7 +242,7 @@ impl std::ops::Div<f64> for Vec3 {
     }
 }
 
-impl<'a> std::ops::Div<f64> for &'a Vec3 {
+impl std::ops::Div<f64> for &Vec3 {
     type Output = Vec3;
     fn div(self, other: f64) -> Vec3 {
         // use the reciprocal of the divisor to avoid division as division is more expensive than
@@ -272,7 +286,7 @@ pub fn dot(v1: &Vec3, v2: &Vec3) -> f64 {
     v1.v[0] * v2.v[0] + v1.v[1] * v2.v[1] + v1.v[2] * v2.v[2]
 }
 
-fn cross(v1: Vec3, v2: Vec3) -> Vec3 {
+pub fn cross(v1: Vec3, v2: Vec3) -> Vec3 {
     Vec3 {
         v: [
             v1.v[1] * v2.v[2] - v1.v[2] * v2.v[1],
@@ -402,6 +416,20 @@ mod tests {
         assert_eq!(exp, res);
     }
 
+    #[test]
+    fn test_mul_vec3_ref() {
+        let (a, b, c) = (4.0, 5.0, 6.0);
+        let (x, y, z) = (1.0, 2.0, 3.0);
+        let v1 = Vec3 { v: [a, b, c] };
+        let v2 = Vec3 { v: [x, y, z] };
+        let exp = Vec3 {
+            v: [a * x, b * y, c * z],
+        };
+        let res = &v1 * &v2;
+
+        assert_eq!(exp, res);
+    }
+
     #[test]
     fn test_div() {
         let (a, b, c) = (4.0, 5.0, 6.0);
@@ -438,6 +466,13 @@ mod tests {
         assert_eq!(exp, res);
     }
 
+    #[test]
+    fn test_max_component() {
+        let v = Vec3 { v: [1.0, 3.0, 2.0] };
+
+        assert_eq!(3.0, v.max_component());
+    }
+
     #[test]
     fn test_index() {
         let (x, y, z) = (1.0, 2.0, 3.0);
@@ -484,6 +519,26 @@ mod tests {
         assert_eq!(exp, res);
     }
 
+    #[test]
+    fn test_on_unit_sphere() {
+        for u in [[0.0, 0.0], [0.25, 0.5], [0.5, 0.75], [0.999, 0.1]] {
+            let v = Vec3::on_unit_sphere(u);
+
+            assert!((v.len() - 1.0).abs() < 1e-12);
+            assert!((v.z() - (1.0 - 2.0 * u[0])).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_random_cosine_direction() {
+        for u in [[0.0, 0.0], [0.25, 0.5], [0.5, 0.75], [0.999, 0.1]] {
+            let v = Vec3::random_cosine_direction(u);
+
+            assert!((v.len() - 1.0).abs() < 1e-12);
+            assert!(v.z() >= 0.0);
+        }
+    }
+
     #[test]
     fn test_unit() {
         let (x, y, z) = (1.0, 2.0, 3.0);